@@ -0,0 +1,67 @@
+// Copyright (c) 2018 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Compares the time taken by sequential and parallel walks of a generated tree.
+//!
+//! Run with `cargo run --release --example parallel --features rayon [FILES]`.
+
+use globwalk::GlobWalkerBuilder;
+use rayon::iter::ParallelIterator;
+use std::fs::{create_dir_all, File};
+use std::time::Instant;
+
+fn main() {
+    let files: usize = std::env::args()
+        .nth(1)
+        .map(|n| n.parse().expect("invalid file count"))
+        .unwrap_or(50_000);
+
+    let dir = tempfile::TempDir::new().expect("failed to create temporary folder");
+    for i in 0..files {
+        let sub = dir.path().join(format!("{}/{}", i % 64, i % 1024));
+        create_dir_all(&sub).expect("failed to create subfolders");
+        let ext = if i % 2 == 0 { "rs" } else { "txt" };
+        File::create(sub.join(format!("{}.{}", i, ext))).expect("failed to create a file");
+    }
+
+    let builder = || GlobWalkerBuilder::new(dir.path(), "**/*.rs");
+
+    let start = Instant::now();
+    let sequential = builder().build().unwrap().filter_map(Result::ok).count();
+    let sequential_time = start.elapsed();
+
+    let start = Instant::now();
+    let parallel = builder()
+        .build_parallel()
+        .unwrap()
+        .filter_map(Result::ok)
+        .count();
+    let parallel_time = start.elapsed();
+
+    assert_eq!(sequential, parallel);
+    println!("{} matches out of {} files", sequential, files);
+    println!("sequential: {:?}", sequential_time);
+    println!(
+        "parallel:   {:?} ({} threads, {:.2}x)",
+        parallel_time,
+        rayon::current_num_threads(),
+        sequential_time.as_secs_f64() / parallel_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,315 @@
+// Copyright (c) 2017 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A parser for a subset of the `find(1)` expression language.
+
+use crate::{DirEntry, FileType};
+use ignore::overrides::{Override, OverrideBuilder};
+use std::time::SystemTime;
+
+/// Error from parsing a `find` expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(String);
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The result of parsing a `find` expression.
+pub(crate) struct FindExpression {
+    pub(crate) min_depth: Option<usize>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) expr: Expr,
+}
+
+pub(crate) enum Expr {
+    True,
+    Name(Override),
+    Type(FileType),
+    Newer(SystemTime),
+    Size { cmp: SizeCmp, n: u64, unit: u64 },
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+pub(crate) enum SizeCmp {
+    Less,
+    Equal,
+    Greater,
+}
+
+impl Expr {
+    pub(crate) fn matches(&self, entry: &DirEntry) -> bool {
+        match self {
+            Expr::True => true,
+            Expr::Name(matcher) => matcher
+                .matched(entry.file_name(), entry.file_type().is_dir())
+                .is_whitelist(),
+            Expr::Type(file_type) => {
+                let ft = entry.file_type();
+                (file_type.contains(FileType::FILE) && ft.is_file())
+                    || (file_type.contains(FileType::DIR) && ft.is_dir())
+                    || (file_type.contains(FileType::SYMLINK) && ft.is_symlink())
+            }
+            Expr::Newer(time) => entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .is_some_and(|modified| modified > *time),
+            Expr::Size { cmp, n, unit } => match entry.metadata() {
+                // Like `find`, sizes are rounded up to the unit.
+                Ok(m) => {
+                    let size = (m.len() + unit - 1) / unit;
+                    match cmp {
+                        SizeCmp::Less => size < *n,
+                        SizeCmp::Equal => size == *n,
+                        SizeCmp::Greater => size > *n,
+                    }
+                }
+                Err(_) => false,
+            },
+            Expr::Not(e) => !e.matches(entry),
+            Expr::And(a, b) => a.matches(entry) && b.matches(entry),
+            Expr::Or(a, b) => a.matches(entry) || b.matches(entry),
+        }
+    }
+}
+
+/// Parse a `find` expression, such as `-name '*.rs' -o -type d`.
+pub(crate) fn parse(args: &str) -> Result<FindExpression, ParseError> {
+    let tokens = tokenize(args)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        min_depth: None,
+        max_depth: None,
+    };
+
+    let expr = if tokens.is_empty() {
+        Expr::True
+    } else {
+        parser.or()?
+    };
+    if let Some(token) = parser.peek() {
+        return Err(ParseError(format!("unexpected `{}`", token)));
+    }
+
+    Ok(FindExpression {
+        min_depth: parser.min_depth,
+        max_depth: parser.max_depth,
+        expr,
+    })
+}
+
+/// Split the arguments on whitespace, honoring single and double quotes.
+fn tokenize(args: &str) -> Result<Vec<String>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut current = None::<String>;
+    let mut quote = None;
+
+    for c in args.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.get_or_insert_with(String::new).push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => tokens.extend(current.take()),
+            None => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(ParseError(String::from("unterminated quote")));
+    }
+
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn advance(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += token.is_some() as usize;
+        token
+    }
+
+    fn argument(&mut self, predicate: &str) -> Result<&'a str, ParseError> {
+        self.advance()
+            .ok_or_else(|| ParseError(format!("missing argument to `{}`", predicate)))
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and()?;
+        while let Some("-o" | "-or") = self.peek() {
+            self.advance();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.not()?;
+        loop {
+            match self.peek() {
+                Some("-a" | "-and") => {
+                    self.advance();
+                }
+                // Juxtaposed primaries are implicitly joined with `-and`.
+                Some(token) if token != "-o" && token != "-or" && token != ")" => {}
+                _ => break,
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some("!" | "-not") => {
+                self.advance();
+                Ok(Expr::Not(Box::new(self.not()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self
+            .advance()
+            .ok_or_else(|| ParseError(String::from("expected an expression")))?;
+
+        match token {
+            "(" => {
+                let expr = self.or()?;
+                match self.advance() {
+                    Some(")") => Ok(expr),
+                    _ => Err(ParseError(String::from("missing `)`"))),
+                }
+            }
+            "-name" | "-iname" => {
+                let pattern = self.argument(token)?;
+                let mut builder = OverrideBuilder::new("/");
+                builder
+                    .case_insensitive(token == "-iname")
+                    .and_then(|b| b.add(pattern))
+                    .map_err(|e| ParseError(format!("invalid pattern `{}`: {}", pattern, e)))?;
+                let matcher = builder
+                    .build()
+                    .map_err(|e| ParseError(format!("invalid pattern `{}`: {}", pattern, e)))?;
+                Ok(Expr::Name(matcher))
+            }
+            "-type" => match self.argument(token)? {
+                "f" => Ok(Expr::Type(FileType::FILE)),
+                "d" => Ok(Expr::Type(FileType::DIR)),
+                "l" => Ok(Expr::Type(FileType::SYMLINK)),
+                other => Err(ParseError(format!("unsupported file type `{}`", other))),
+            },
+            "-maxdepth" | "-mindepth" => {
+                let arg = self.argument(token)?;
+                let depth = arg
+                    .parse()
+                    .map_err(|_| ParseError(format!("invalid depth `{}`", arg)))?;
+                if token == "-maxdepth" {
+                    self.max_depth = Some(depth);
+                } else {
+                    self.min_depth = Some(depth);
+                }
+                Ok(Expr::True)
+            }
+            "-newer" => {
+                let file = self.argument(token)?;
+                let modified = std::fs::metadata(file)
+                    .and_then(|m| m.modified())
+                    .map_err(|e| ParseError(format!("cannot read `{}`: {}", file, e)))?;
+                Ok(Expr::Newer(modified))
+            }
+            "-size" => {
+                let arg = self.argument(token)?;
+                parse_size(arg).ok_or_else(|| ParseError(format!("invalid size `{}`", arg)))
+            }
+            other => Err(ParseError(format!("unsupported predicate `{}`", other))),
+        }
+    }
+}
+
+/// Parse the argument of `-size`, e.g. `+10M`.
+fn parse_size(arg: &str) -> Option<Expr> {
+    let (cmp, arg) = match arg.as_bytes().first()? {
+        b'+' => (SizeCmp::Greater, &arg[1..]),
+        b'-' => (SizeCmp::Less, &arg[1..]),
+        _ => (SizeCmp::Equal, arg),
+    };
+    let (n, unit) = match arg.char_indices().last()? {
+        (i, 'c') => (&arg[..i], 1),
+        (i, 'w') => (&arg[..i], 2),
+        (i, 'b') => (&arg[..i], 512),
+        (i, 'k') => (&arg[..i], 1024),
+        (i, 'M') => (&arg[..i], 1024 * 1024),
+        (i, 'G') => (&arg[..i], 1024 * 1024 * 1024),
+        _ => (arg, 512),
+    };
+
+    Some(Expr::Size {
+        cmp,
+        n: n.parse().ok()?,
+        unit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"-name '*.rs' -o -name "a b""#).unwrap(),
+            ["-name", "*.rs", "-o", "-name", "a b"]
+        );
+        assert!(tokenize("-name '*.rs").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("-exec rm {} ;").is_err());
+        assert!(parse("-name").is_err());
+        assert!(parse("-type x").is_err());
+        assert!(parse("-size 10X").is_err());
+        assert!(parse("( -name a").is_err());
+        assert!(parse("-maxdepth 2 -type f").is_ok());
+    }
+}
@@ -49,6 +49,28 @@
 //! # fn main() { run().unwrap() }
 //! ```
 //!
+//! ## Searching a given directory
+//!
+//! [`glob_with_base`] and [`glob_many`] search a base directory other than the current one,
+//! for one pattern or for several.
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["cow.jpg", "cat.gif", "notes.txt"])?;
+//! # let BASE_DIR = &temp_dir;
+//! let images = globwalk::glob_with_base(BASE_DIR, "*.{png,jpg,gif}")?;
+//! assert_eq!(images.count(), 2);
+//!
+//! let images = globwalk::glob_many(BASE_DIR, &["*.{png,jpg,gif}", "!cow.*"])?;
+//! assert_eq!(images.count(), 1);
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
+//!
+//! [`glob_with_base`]: fn.glob_with_base.html
+//! [`glob_many`]: fn.glob_many.html
+//!
 //! ## Advanced Globbing ###
 //!
 //! By using one of the constructors of `globwalk::GlobWalker`, it is possible to alter the
@@ -79,24 +101,337 @@
 //! # Ok(()) }
 //! # fn main() { run().unwrap() }
 //! ```
+//!
+//! ## Excluding paths
+//!
+//! Patterns starting with `!` exclude the paths they match. As in `.gitignore` files, the last
+//! pattern matching a path decides whether it is yielded, and a pattern without a slash matches
+//! a file name at any depth.
+//!
+//! Excluding a specific file, wherever it is:
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["hello.rs", "world.rs", "src/world.rs"])?;
+//! # let BASE_DIR = &temp_dir;
+//! let walker = globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["**/*.rs", "!world.rs"])
+//!     .build()?;
+//! // Only `hello.rs`; `!/world.rs` would only have excluded the top-level `world.rs`.
+//! assert_eq!(walker.count(), 1);
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
+//!
+//! Excluding the files directly inside a directory:
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["a.png", "Pictures/b.png", "Pictures/2020/c.png"])?;
+//! # let BASE_DIR = &temp_dir;
+//! let walker =
+//!     globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["**/*.png", "!Pictures/*.png"])
+//!         .build()?;
+//! // `a.png` and `Pictures/2020/c.png`. Note that `!Pictures/*` would also have matched, and
+//! // therefore excluded, the `Pictures/2020` directory.
+//! assert_eq!(walker.count(), 2);
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
+//!
+//! Excluding a directory along with all of its contents, which are then never read:
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["a.png", "Pictures/b.png", "Pictures/2020/c.png"])?;
+//! # let BASE_DIR = &temp_dir;
+//! let walker = globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["**/*.png", "!Pictures"])
+//!     .build()?;
+//! // Only `a.png`.
+//! assert_eq!(walker.count(), 1);
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
+//!
+//! Re-including a file after a broad exclusion, by adding a pattern after it. This only works if
+//! the directory containing the file is not excluded itself, which is why `tests/**` is used here
+//! rather than `tests`:
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["lib.rs", "tests/common.rs", "tests/a.rs", "tests/b/c.rs"])?;
+//! # let BASE_DIR = &temp_dir;
+//! let walker = globwalk::GlobWalkerBuilder::from_patterns(
+//!     BASE_DIR,
+//!     &["**/*.rs", "!tests/**", "tests/common.rs"],
+//! )
+//! .build()?;
+//! // `lib.rs` and `tests/common.rs`.
+//! assert_eq!(walker.count(), 2);
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
+//!
+//! # Cargo features
+//!
+//! - `unicode-normalization`: enables [`GlobWalkerBuilder::normalize_unicode`].
+//! - `rayon`: enables [`GlobWalkerBuilder::build_parallel`], and allows collecting a
+//!   [`GlobWalkerResult`] from a parallel iterator.
+//! - `rand`: enables [`GlobWalker::sample`].
+//! - `sha2`: enables [`GlobWalker::hash_walk`].
+//! - `async`: enables [`glob_stream`].
+//! - `tokio`: enables [`GlobWalkerBuilder::build_async`].
+//! - `glob`: enables [`GlobWalkerBuilder::new_with_root_pattern`].
+//! - `icu_collator`: enables [`GlobWalkerBuilder::sort_by_locale_name`].
+//! - `serde`: implements `Serialize` and `Deserialize` for [`GlobWalkerConfig`] and
+//!   [`WalkCheckpoint`].
+//!
+//! [`GlobWalkerBuilder::normalize_unicode`]: struct.GlobWalkerBuilder.html#method.normalize_unicode
+//! [`GlobWalkerBuilder::build_parallel`]: struct.GlobWalkerBuilder.html#method.build_parallel
+//! [`GlobWalker::sample`]: struct.GlobWalker.html#method.sample
+//! [`GlobWalker::hash_walk`]: struct.GlobWalker.html#method.hash_walk
+//! [`glob_stream`]: fn.glob_stream.html
+//! [`GlobWalkerBuilder::build_async`]: struct.GlobWalkerBuilder.html#method.build_async
+//! [`GlobWalkerBuilder::new_with_root_pattern`]: struct.GlobWalkerBuilder.html#method.new_with_root_pattern
+//! [`GlobWalkerBuilder::sort_by_locale_name`]: struct.GlobWalkerBuilder.html#method.sort_by_locale_name
+//! [`GlobWalkerResult`]: struct.GlobWalkerResult.html
+//! [`GlobWalkerConfig`]: struct.GlobWalkerConfig.html
+//!
+//! ## Thread safety
+//!
+//! `GlobWalkerBuilder`, `GlobWalker` and the iterators built from them are `Send` and `Sync`, so
+//! they can be moved to other threads, or stored in types that are shared between threads. The
+//! callbacks stored by the builder, such as sorting functions, entry filters and miss loggers,
+//! must be `Send` and `Sync` for that reason, and may be called from the threads of
+//! multi-threaded walks.
+//!
+//! ## Parallel processing
+//!
+//! `GlobWalker` is `Send`, so it can be bridged into a `rayon` parallel iterator in order to
+//! process the matched files concurrently, while the walk itself stays sequential. To walk the
+//! tree in parallel as well, use [`GlobWalkerBuilder::build_parallel`] instead.
+//!
+//! ```rust
+//! # include!("doctests.rs");
+//! # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+//! # let temp_dir = create_files(&["main.rs", "lib/a.rs", "README.md"])?;
+//! # let BASE_DIR = &temp_dir;
+//! # #[cfg(feature = "rayon")] {
+//! use rayon::iter::{ParallelBridge, ParallelIterator};
+//!
+//! let result: globwalk::GlobWalkerResult = globwalk::GlobWalkerBuilder::new(BASE_DIR, "**/*.rs")
+//!     .build()?
+//!     .par_bridge()
+//!     .inspect(|entry| {
+//!         if let Ok(entry) = entry {
+//!             println!("{}", entry.path().display());
+//!         }
+//!     })
+//!     .collect();
+//! assert_eq!(result.entries.len(), 2);
+//! # }
+//! # Ok(()) }
+//! # fn main() { run().unwrap() }
+//! ```
 
 // Our doctests need main to compile; AFAICT this is a false positive generated by clippy
 #![allow(clippy::needless_doctest_main)]
 #![warn(missing_docs)]
 
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::overrides::{Override, OverrideBuilder};
 use ignore::Match;
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use traversal::{BreadthFirst, Shard, Traversal, WalkDirConfig};
 use walkdir::WalkDir;
 
+mod find;
+mod multi;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "tokio")]
+mod stream;
+mod traversal;
+
+pub use find::ParseError;
+pub use multi::{GlobWalkerMulti, GlobWalkerMultiBuilder};
+#[cfg(feature = "rayon")]
+pub use parallel::ParallelGlobWalker;
+#[cfg(feature = "tokio")]
+pub use stream::GlobWalkerStream;
+
 /// Error from parsing globs.
 #[derive(Debug)]
 pub struct GlobError(ignore::Error);
 
 /// Error from iterating on files.
-pub type WalkError = walkdir::Error;
+///
+/// Most errors come from reading the file system, and behave like `walkdir::Error`. A walker
+/// built with [`GlobWalkerBuilder::with_error_limit`] may also yield a final error when too many
+/// errors were encountered.
+///
+/// # Migrating from 0.9
+///
+/// Up to globwalk 0.9, this was an alias of `walkdir::Error`. It is now a type of its own, since
+/// some errors, such as the final error of an error limit, do not come from `walkdir`. Code that
+/// called the methods of `walkdir::Error` keeps working, as they are mirrored here: [`path`],
+/// [`depth`], [`loop_ancestor`], [`io_error`] and [`into_io_error`]. A `walkdir::Error` is
+/// converted into a `WalkError` with `From`, e.g. by `?`, but there is no conversion the other
+/// way; code that needs a `walkdir::Error` should use these methods, or convert the error into a
+/// `std::io::Error` instead.
+///
+/// [`GlobWalkerBuilder::with_error_limit`]: struct.GlobWalkerBuilder.html#method.with_error_limit
+/// [`path`]: #method.path
+/// [`depth`]: #method.depth
+/// [`loop_ancestor`]: #method.loop_ancestor
+/// [`io_error`]: #method.io_error
+/// [`into_io_error`]: #method.into_io_error
+#[derive(Debug)]
+pub struct WalkError(WalkErrorInner);
+
+#[derive(Debug)]
+enum WalkErrorInner {
+    Walk(walkdir::Error),
+    /// An error from checking an entry that was read successfully, e.g. its metadata.
+    Io {
+        path: PathBuf,
+        depth: usize,
+        err: std::io::Error,
+    },
+    TooManyErrors {
+        count: usize,
+        last: Box<WalkError>,
+    },
+}
+
+impl WalkError {
+    fn io(entry: &DirEntry, err: std::io::Error) -> Self {
+        WalkError(WalkErrorInner::Io {
+            path: entry.path().to_owned(),
+            depth: entry.depth(),
+            err,
+        })
+    }
+
+    /// The path associated with this error, if any.
+    ///
+    /// For the final error of a walk with an error limit, this is the path of the last error.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.path(),
+            WalkErrorInner::Io { path, .. } => Some(path),
+            WalkErrorInner::TooManyErrors { last, .. } => last.path(),
+        }
+    }
+
+    /// The depth at which this error occurred, relative to the base directory.
+    pub fn depth(&self) -> usize {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.depth(),
+            WalkErrorInner::Io { depth, .. } => *depth,
+            WalkErrorInner::TooManyErrors { last, .. } => last.depth(),
+        }
+    }
+
+    /// If this error was caused by a symbolic link loop, the path of the ancestor the link points
+    /// back to.
+    pub fn loop_ancestor(&self) -> Option<&Path> {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.loop_ancestor(),
+            WalkErrorInner::Io { .. } | WalkErrorInner::TooManyErrors { .. } => None,
+        }
+    }
+
+    /// The underlying I/O error, if this error was caused by one.
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.io_error(),
+            WalkErrorInner::Io { err, .. } => Some(err),
+            WalkErrorInner::TooManyErrors { .. } => None,
+        }
+    }
+
+    /// Same as [`io_error`], but takes ownership of the error.
+    ///
+    /// [`io_error`]: #method.io_error
+    pub fn into_io_error(self) -> Option<std::io::Error> {
+        match self.0 {
+            WalkErrorInner::Walk(e) => e.into_io_error(),
+            WalkErrorInner::Io { err, .. } => Some(err),
+            WalkErrorInner::TooManyErrors { .. } => None,
+        }
+    }
+
+    /// If the walk was stopped because of too many errors, the number of errors encountered.
+    ///
+    /// See [`GlobWalkerBuilder::with_error_limit`].
+    ///
+    /// [`GlobWalkerBuilder::with_error_limit`]: struct.GlobWalkerBuilder.html#method.with_error_limit
+    pub fn error_count(&self) -> Option<usize> {
+        match &self.0 {
+            WalkErrorInner::Walk(_) | WalkErrorInner::Io { .. } => None,
+            WalkErrorInner::TooManyErrors { count, .. } => Some(*count),
+        }
+    }
+}
+
+impl From<walkdir::Error> for WalkError {
+    fn from(e: walkdir::Error) -> Self {
+        WalkError(WalkErrorInner::Walk(e))
+    }
+}
+
+impl From<WalkError> for std::io::Error {
+    fn from(e: WalkError) -> Self {
+        match e.0 {
+            WalkErrorInner::Walk(e) => e.into(),
+            WalkErrorInner::Io { err, .. } => err,
+            WalkErrorInner::TooManyErrors { .. } => {
+                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WalkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.fmt(f),
+            WalkErrorInner::Io { path, err, .. } => {
+                write!(f, "IO error for operation on {}: {}", path.display(), err)
+            }
+            WalkErrorInner::TooManyErrors { count, last } => {
+                write!(
+                    f,
+                    "walk aborted after {} errors, the last one being: {}",
+                    count, last
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for WalkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.0 {
+            WalkErrorInner::Walk(e) => e.source(),
+            WalkErrorInner::Io { err, .. } => Some(err),
+            WalkErrorInner::TooManyErrors { last, .. } => Some(last.as_ref()),
+        }
+    }
+}
+
 /// A directory entry.
 ///
 /// This is the type of value that is yielded from the iterators defined in this crate.
@@ -124,7 +459,139 @@ impl std::fmt::Display for GlobError {
     }
 }
 
-impl std::error::Error for GlobError {}
+impl std::error::Error for GlobError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Either an error from building a walker, or an error from walking.
+///
+/// This is returned by the methods that both build and iterate a walker, such as
+/// [`GlobWalkerBuilder::try_build_and_collect`], so that both kinds of errors can be handled with
+/// `?` in a single function.
+///
+/// [`GlobWalkerBuilder::try_build_and_collect`]: struct.GlobWalkerBuilder.html#method.try_build_and_collect
+#[derive(Debug)]
+pub enum GlobWalkError {
+    /// The walker could not be built, e.g. because of an invalid pattern.
+    Build(GlobError),
+    /// An error was encountered while walking.
+    Walk(WalkError),
+}
+
+impl From<GlobError> for GlobWalkError {
+    fn from(e: GlobError) -> Self {
+        GlobWalkError::Build(e)
+    }
+}
+
+impl From<WalkError> for GlobWalkError {
+    fn from(e: WalkError) -> Self {
+        GlobWalkError::Walk(e)
+    }
+}
+
+impl std::fmt::Display for GlobWalkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            GlobWalkError::Build(e) => e.fmt(f),
+            GlobWalkError::Walk(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for GlobWalkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GlobWalkError::Build(e) => Some(e),
+            GlobWalkError::Walk(e) => Some(e),
+        }
+    }
+}
+
+/// Annotate an error from adding `pattern` to the matcher with the position of its first
+/// unmatched brace, if any.
+///
+/// The messages of `ignore` only say that a brace is unmatched, which is hard to act on for long
+/// patterns with several alternate groups. Other errors are returned unchanged.
+fn pattern_error_with_context(pattern: &str, inner: ignore::Error) -> GlobError {
+    if !matches!(inner, ignore::Error::Glob { .. }) {
+        return GlobError(inner);
+    }
+
+    let mut open = Vec::new();
+    let mut unmatched = None;
+    let mut chars = pattern.chars().enumerate().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if cfg!(not(windows)) => {
+                chars.next();
+            }
+            // Braces are literal inside of a character class, and so is a leading `]`.
+            '[' => {
+                chars.next_if(|&(_, c)| c == '!' || c == '^');
+                chars.next_if(|&(_, c)| c == ']');
+                for (_, c) in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            '{' => open.push(i),
+            // Otherwise, this closes the innermost open group.
+            '}' if open.pop().is_none() => {
+                unmatched = Some((i, '}'));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    match unmatched.or_else(|| open.pop().map(|i| (i, '{'))) {
+        Some((i, brace)) => GlobError(ignore::Error::Glob {
+            glob: None,
+            err: format!(
+                "in pattern `{}` at position {}: unmatched `{}`",
+                pattern,
+                i + 1,
+                brace
+            ),
+        }),
+        None => GlobError(inner),
+    }
+}
+
+/// The outcome of a walk, with the matched entries separated from the errors.
+///
+/// This can be collected from any iterator over the items yielded by a `GlobWalker`, and, with the
+/// `rayon` feature enabled, from a parallel iterator as well.
+#[derive(Debug, Default)]
+pub struct GlobWalkerResult {
+    /// The matched entries, in the order they were yielded.
+    pub entries: Vec<DirEntry>,
+    /// The errors encountered during the walk, in the order they were yielded.
+    pub errors: Vec<WalkError>,
+}
+
+impl Extend<Result<DirEntry, WalkError>> for GlobWalkerResult {
+    fn extend<I: IntoIterator<Item = Result<DirEntry, WalkError>>>(&mut self, iter: I) {
+        for item in iter {
+            match item {
+                Ok(e) => self.entries.push(e),
+                Err(e) => self.errors.push(e),
+            }
+        }
+    }
+}
+
+impl FromIterator<Result<DirEntry, WalkError>> for GlobWalkerResult {
+    fn from_iter<I: IntoIterator<Item = Result<DirEntry, WalkError>>>(iter: I) -> Self {
+        let mut result = GlobWalkerResult::default();
+        result.extend(iter);
+        result
+    }
+}
 
 bitflags::bitflags! {
     /// Possible file type filters.
@@ -132,6 +599,7 @@ bitflags::bitflags! {
     ///
     /// Note that not all files are represented in this enum.
     /// For example, a char-device is neither a file, a directory, nor a symlink.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
     pub struct FileType: u32 {
         #[allow(missing_docs)] const FILE =    0b001;
         #[allow(missing_docs)] const DIR =     0b010;
@@ -139,686 +607,7960 @@ bitflags::bitflags! {
     }
 }
 
-/// An iterator for recursively yielding glob matches.
-///
-/// The order of elements yielded by this iterator is unspecified.
-pub struct GlobWalkerBuilder {
-    root: PathBuf,
-    patterns: Vec<String>,
-    walker: WalkDir,
-    case_insensitive: bool,
-    file_type: Option<FileType>,
+/// The order in which the directory tree is traversed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraversalOrder {
+    /// Visit the contents of each directory before moving on to its siblings.
+    ///
+    /// This is the default.
+    #[default]
+    DepthFirst,
+    /// Visit all entries at a given depth before any entry that is deeper.
+    ///
+    /// Each level is read by a separate walk from the base directory, trading additional system
+    /// calls for the ability to yield shallow matches first.
+    BreadthFirst,
 }
 
-impl GlobWalkerBuilder {
-    /// Construct a new `GlobWalker` with a glob pattern.
+/// A unicode normalization form, applied to patterns and paths before they are matched.
+///
+/// Different platforms store the same file name in different forms; most notably, macOS uses a
+/// decomposed form (NFD) while Linux and Windows typically use the composed form (NFC).
+/// Normalizing both sides to the same form makes matching consistent across platforms.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnicodeNormalization {
+    /// Canonical composition.
+    Nfc,
+    /// Canonical decomposition.
+    Nfd,
+    /// Compatibility composition.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+    /// Match patterns and paths as-is.
     ///
-    /// When iterated, the `base` directory will be recursively searched for paths
-    /// matching `pattern`.
-    pub fn new<P, S>(base: P, pattern: S) -> Self
-    where
-        P: AsRef<Path>,
-        S: AsRef<str>,
-    {
-        GlobWalkerBuilder::from_patterns(base, &[pattern])
-    }
+    /// This is the default.
+    #[default]
+    None,
+}
 
-    /// Construct a new `GlobWalker` from a list of patterns.
-    ///
-    /// When iterated, the `base` directory will be recursively searched for paths
-    /// matching `patterns`.
-    pub fn from_patterns<P, S>(base: P, patterns: &[S]) -> Self
-    where
-        P: AsRef<Path>,
-        S: AsRef<str>,
-    {
-        fn normalize_pattern<S: AsRef<str>>(pattern: S) -> String {
-            // Either `ignore` or our iteration code treat a single asterisk pretty strangely, matching everything, even
-            // paths that are inside a sub-direcrtory.
-            if pattern.as_ref() == "*" {
-                String::from("/*")
-            } else {
-                pattern.as_ref().to_owned()
-            }
-        }
-        GlobWalkerBuilder {
-            root: base.as_ref().into(),
-            patterns: patterns.iter().map(normalize_pattern).collect::<_>(),
-            walker: WalkDir::new(base),
-            case_insensitive: false,
-            file_type: None,
+#[cfg(feature = "unicode-normalization")]
+impl UnicodeNormalization {
+    fn normalize(self, s: &str) -> String {
+        use unicode_normalization::UnicodeNormalization as _;
+
+        match self {
+            UnicodeNormalization::Nfc => s.nfc().collect(),
+            UnicodeNormalization::Nfd => s.nfd().collect(),
+            UnicodeNormalization::Nfkc => s.nfkc().collect(),
+            UnicodeNormalization::Nfkd => s.nfkd().collect(),
+            UnicodeNormalization::None => s.to_owned(),
         }
     }
 
-    /// Set the minimum depth of entries yielded by the iterator.
-    ///
-    /// The smallest depth is `0` and always corresponds to the path given
-    /// to the `new` function on this type. Its direct descendents have depth
-    /// `1`, and their descendents have depth `2`, and so on.
-    pub fn min_depth(mut self, depth: usize) -> Self {
-        self.walker = self.walker.min_depth(depth);
-        self
+    /// Returns `None` if the path is left untouched.
+    fn normalize_path(self, path: &Path) -> Option<PathBuf> {
+        match (self, path.to_str()) {
+            (UnicodeNormalization::None, _) | (_, None) => None,
+            (_, Some(s)) => Some(self.normalize(s).into()),
+        }
     }
+}
 
-    /// Set the maximum depth of entries yield by the iterator.
+/// The reason an entry visited by a walker was not yielded.
+///
+/// See [`GlobWalker::log_misses`].
+///
+/// [`GlobWalker::log_misses`]: struct.GlobWalker.html#method.log_misses
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The entry was excluded by a negated pattern.
     ///
-    /// The smallest depth is `0` and always corresponds to the path given
-    /// to the `new` function on this type. Its direct descendents have depth
-    /// `1`, and their descendents have depth `2`, and so on.
+    /// If the entry is a directory, its contents are not visited either.
+    PatternNegation {
+        /// The negated pattern that excluded the entry.
+        pattern: String,
+    },
+    /// The entry is shallower than the minimum depth.
+    DepthLimit,
+    /// The entry matched the patterns, but not the file type filter.
+    FileTypeFilter,
+    /// The entry matched the patterns, but was rejected by a filter on its metadata.
+    MetadataFilter {
+        /// A description of the filter that rejected the entry.
+        reason: String,
+    },
+    /// The entry was not matched by any pattern.
+    NoMatch,
+    /// The name of the entry starts with a `.`, and [`GlobWalkerBuilder::skip_hidden`] was
+    /// enabled.
     ///
-    /// Note that this will not simply filter the entries of the iterator, but
-    /// it will actually avoid descending into directories when the depth is
-    /// exceeded.
-    pub fn max_depth(mut self, depth: usize) -> Self {
-        self.walker = self.walker.max_depth(depth);
-        self
-    }
-
-    /// Follow symbolic links. By default, this is disabled.
+    /// If the entry is a directory, its contents are not visited either.
     ///
-    /// When `yes` is `true`, symbolic links are followed as if they were
-    /// normal directories and files. If a symbolic link is broken or is
-    /// involved in a loop, an error is yielded.
+    /// [`GlobWalkerBuilder::skip_hidden`]: struct.GlobWalkerBuilder.html#method.skip_hidden
+    Hidden,
+    /// The entry is listed in a `.gitignore` file, and [`GlobWalkerBuilder::with_gitignore`] was
+    /// enabled.
     ///
-    /// When enabled, the yielded [`DirEntry`] values represent the target of
-    /// the link while the path corresponds to the link. See the [`DirEntry`]
-    /// type for more details.
+    /// If the entry is a directory, its contents are not visited either.
     ///
-    /// [`DirEntry`]: struct.DirEntry.html
-    pub fn follow_links(mut self, yes: bool) -> Self {
-        self.walker = self.walker.follow_links(yes);
-        self
-    }
-
-    /// Set the maximum number of simultaneously open file descriptors used
-    /// by the iterator.
+    /// [`GlobWalkerBuilder::with_gitignore`]: struct.GlobWalkerBuilder.html#method.with_gitignore
+    Gitignored,
+    /// The entry was rejected by the callback given to [`GlobWalkerBuilder::filter_entry`].
     ///
-    /// `n` must be greater than or equal to `1`. If `n` is `0`, then it is set
-    /// to `1` automatically. If this is not set, then it defaults to some
-    /// reasonably low number.
+    /// If the entry is a directory, its contents are not visited either.
     ///
-    /// This setting has no impact on the results yielded by the iterator
-    /// (even when `n` is `1`). Instead, this setting represents a trade off
-    /// between scarce resources (file descriptors) and memory. Namely, when
-    /// the maximum number of file descriptors is reached and a new directory
-    /// needs to be opened to continue iteration, then a previous directory
-    /// handle is closed and has its unyielded entries stored in memory. In
-    /// practice, this is a satisfying trade off because it scales with respect
-    /// to the *depth* of your file tree. Therefore, low values (even `1`) are
-    /// acceptable.
+    /// [`GlobWalkerBuilder::filter_entry`]: struct.GlobWalkerBuilder.html#method.filter_entry
+    EntryFilter,
+    /// The entry is a directory that was already visited through another path, and
+    /// [`GlobWalkerBuilder::detect_cycles`] was enabled.
     ///
-    /// Note that this value does not impact the number of system calls made by
-    /// an exhausted iterator.
+    /// Its contents are not visited again.
     ///
-    /// # Platform behavior
+    /// [`GlobWalkerBuilder::detect_cycles`]: struct.GlobWalkerBuilder.html#method.detect_cycles
+    AlreadyVisited,
+    /// The entry is the same file as an entry that was already yielded through another path,
+    /// such as another hard link, and [`GlobWalkerBuilder::deduplicate_inodes`] was enabled.
     ///
-    /// On Windows, if `follow_links` is enabled, then this limit is not
-    /// respected. In particular, the maximum number of file descriptors opened
-    /// is proportional to the depth of the directory tree traversed.
-    pub fn max_open(mut self, n: usize) -> Self {
-        self.walker = self.walker.max_open(n);
-        self
-    }
-
-    /// Set a function for sorting directory entries.
+    /// [`GlobWalkerBuilder::deduplicate_inodes`]: struct.GlobWalkerBuilder.html#method.deduplicate_inodes
+    DuplicateInode,
+    /// The entry is a directory matched by a pattern given to
+    /// [`GlobWalkerBuilder::skip_dirs_matching`].
     ///
-    /// If a compare function is set, the resulting iterator will return all
-    /// paths in sorted order. The compare function will be called to compare
-    /// entries from the same directory.
-    pub fn sort_by<F>(mut self, cmp: F) -> Self
-    where
-        F: FnMut(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
-    {
-        self.walker = self.walker.sort_by(cmp);
-        self
-    }
-
-    /// Yield a directory's contents before the directory itself. By default,
-    /// this is disabled.
+    /// Its contents are not visited either.
     ///
-    /// When `yes` is `false` (as is the default), the directory is yielded
-    /// before its contents are read. This is useful when, e.g. you want to
-    /// skip processing of some directories.
+    /// [`GlobWalkerBuilder::skip_dirs_matching`]: struct.GlobWalkerBuilder.html#method.skip_dirs_matching
+    SkippedDir {
+        /// The pattern that matched the directory.
+        pattern: String,
+    },
+    /// The entry is a directory with more entries than allowed by
+    /// [`GlobWalkerBuilder::skip_dirs_larger_than`].
     ///
-    /// When `yes` is `true`, the iterator yields the contents of a directory
-    /// before yielding the directory itself. This is useful when, e.g. you
-    /// want to recursively delete a directory.
-    pub fn contents_first(mut self, yes: bool) -> Self {
-        self.walker = self.walker.contents_first(yes);
-        self
-    }
-
-    /// Toggle whether the globs should be matched case insensitively or not.
+    /// Its contents are not visited either.
     ///
-    /// This is disabled by default.
-    pub fn case_insensitive(mut self, yes: bool) -> Self {
-        self.case_insensitive = yes;
-        self
-    }
+    /// [`GlobWalkerBuilder::skip_dirs_larger_than`]: struct.GlobWalkerBuilder.html#method.skip_dirs_larger_than
+    LargeDir,
+    /// The path of the entry is not valid UTF-8, and [`FilenameEncoding::Utf8`] was requested.
+    ///
+    /// [`FilenameEncoding::Utf8`]: enum.FilenameEncoding.html#variant.Utf8
+    InvalidUtf8,
+}
 
-    /// Toggle filtering by file type.
-    /// `FileType` can be an OR of several types.
+/// Controls how entries whose paths are not valid UTF-8 are handled.
+///
+/// Note that the yielded [`DirEntry`] values are never altered; this only affects which entries
+/// are yielded and what path the patterns are matched against.
+///
+/// [`DirEntry`]: type.DirEntry.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilenameEncoding {
+    /// Skip entries whose path is not valid UTF-8, along with the contents of such directories.
     ///
-    /// Note that not all file-types can be whitelisted by this filter (e.g. char-devices, fifos, etc.)
-    pub fn file_type(mut self, file_type: FileType) -> Self {
-        self.file_type = Some(file_type);
-        self
+    /// Every yielded entry is then guaranteed to have a path for which `to_str()` returns `Some`.
+    Utf8,
+    /// Match and yield paths as they are reported by the operating system.
+    ///
+    /// This is the default.
+    #[default]
+    OsString,
+    /// Match patterns against a lossy UTF-8 conversion of the path, where invalid sequences are
+    /// replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+}
+
+/// A pattern, along with the group it was added in.
+#[derive(Clone, Debug)]
+struct Pattern {
+    pattern: String,
+    /// The pattern as it was given, before it was adjusted for matching.
+    original: String,
+    group: Option<String>,
+    priority: i32,
+    /// The position of the pattern in the order the patterns were added, before they are sorted
+    /// by priority.
+    index: usize,
+}
+
+impl Pattern {
+    fn new<S: AsRef<str>>(pattern: S, group: Option<&str>) -> Self {
+        // Either `ignore` or our iteration code treat a single asterisk pretty strangely, matching everything, even
+        // paths that are inside a sub-direcrtory.
+        let original = pattern.as_ref().to_owned();
+        let pattern = if original == "*" {
+            String::from("/*")
+        } else {
+            original.clone()
+        };
+
+        Pattern {
+            pattern,
+            original,
+            group: group.map(ToOwned::to_owned),
+            priority: 0,
+            index: 0,
+        }
     }
 
-    /// Finalize and build a `GlobWalker` instance.
-    pub fn build(self) -> Result<GlobWalker, GlobError> {
-        let mut builder = OverrideBuilder::new(self.root);
+    /// Replace a leading `./` or `../` in the pattern with an anchored path relative to `root`,
+    /// as if the pattern was written relative to `dir`.
+    fn resolve_relative_to(&mut self, dir: &Path, root: &Path) -> Result<(), GlobError> {
+        let (negation, body) = match self.pattern.strip_prefix('!') {
+            Some(body) => ("!", body),
+            None => ("", self.pattern.as_str()),
+        };
 
-        builder
-            .case_insensitive(self.case_insensitive)
-            .map_err(GlobError)?;
+        let mut components: Vec<&str> = body.split('/').collect();
+        let prefix_len = components
+            .iter()
+            .take_while(|c| **c == "." || **c == "..")
+            .count();
+        if prefix_len == 0 {
+            return Ok(());
+        }
 
-        for pattern in self.patterns {
-            builder.add(pattern.as_ref()).map_err(GlobError)?;
+        let prefix: PathBuf = components.drain(..prefix_len).collect();
+        let resolved = normalize_lexically(&dir.join(prefix));
+        let relative = resolved
+            .strip_prefix(normalize_lexically(root))
+            .map_err(|_| {
+                GlobError(ignore::Error::Glob {
+                    glob: Some(self.pattern.clone()),
+                    err: format!("resolves outside of the base directory {}", root.display()),
+                })
+            })?;
+
+        let mut anchored: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        anchored.extend(components.into_iter().map(ToOwned::to_owned));
+        if anchored.iter().all(String::is_empty) {
+            anchored = vec![String::from("**")];
         }
 
-        Ok(GlobWalker {
-            ignore: builder.build().map_err(GlobError)?,
-            walker: self.walker.into_iter(),
-            file_type_filter: self.file_type,
-        })
+        self.pattern = format!("{}/{}", negation, anchored.join("/"));
+        Ok(())
     }
 }
 
-/// An iterator which emits glob-matched patterns.
+/// Resolve `.` and `..` components without touching the file system.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// The settings of a `GlobWalkerBuilder` that can be expressed as plain data.
 ///
-/// An instance of this type must be constructed through `GlobWalker`,
-/// which uses a builder-style pattern.
+/// This allows storing walker settings in configuration files; with the `serde` feature, it
+/// implements `Serialize` and `Deserialize`, and missing fields take their default values. See
+/// [`GlobWalkerBuilder::from_config`] and [`GlobWalkerBuilder::to_config`].
 ///
-/// The order of the yielded paths is undefined, unless specified by the user
-/// using `GlobWalker::sort_by`.
-pub struct GlobWalker {
-    ignore: Override,
-    walker: walkdir::IntoIter,
-    file_type_filter: Option<FileType>,
+/// [`GlobWalkerBuilder::from_config`]: struct.GlobWalkerBuilder.html#method.from_config
+/// [`GlobWalkerBuilder::to_config`]: struct.GlobWalkerBuilder.html#method.to_config
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GlobWalkerConfig {
+    /// The base directory of the walk.
+    pub root: PathBuf,
+    /// The patterns to match, in order.
+    pub patterns: Vec<String>,
+    /// See [`GlobWalkerBuilder::min_depth`](struct.GlobWalkerBuilder.html#method.min_depth).
+    pub min_depth: usize,
+    /// See [`GlobWalkerBuilder::max_depth`](struct.GlobWalkerBuilder.html#method.max_depth).
+    pub max_depth: usize,
+    /// See [`GlobWalkerBuilder::follow_links`](struct.GlobWalkerBuilder.html#method.follow_links).
+    pub follow_links: bool,
+    /// See [`GlobWalkerBuilder::max_open`](struct.GlobWalkerBuilder.html#method.max_open).
+    pub max_open: Option<usize>,
+    /// See [`GlobWalkerBuilder::case_insensitive`](struct.GlobWalkerBuilder.html#method.case_insensitive).
+    pub case_insensitive: bool,
 }
 
-impl Iterator for GlobWalker {
-    type Item = Result<DirEntry, WalkError>;
+impl Default for GlobWalkerConfig {
+    fn default() -> Self {
+        GlobWalkerConfig {
+            root: PathBuf::from("."),
+            patterns: Vec::new(),
+            min_depth: 0,
+            max_depth: usize::MAX,
+            follow_links: false,
+            max_open: None,
+            case_insensitive: false,
+        }
+    }
+}
 
-    // Possible optimization - Do not descend into directory that will never be a match
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut skip_dir = false;
+/// The position of a walk, from which another walk can be resumed.
+///
+/// This is returned by [`GlobWalker::checkpoint`], and given to
+/// [`GlobWalkerBuilder::resume_from`]. With the `serde` feature, it implements `Serialize` and
+/// `Deserialize`, so that it can be stored across process restarts.
+///
+/// [`GlobWalker::checkpoint`]: struct.GlobWalker.html#method.checkpoint
+/// [`GlobWalkerBuilder::resume_from`]: struct.GlobWalkerBuilder.html#method.resume_from
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WalkCheckpoint {
+    path: PathBuf,
+}
 
-        // The outer loop allows us to avoid multiple mutable borrows on `self.walker` when
-        // we want to skip.
-        'skipper: loop {
-            if skip_dir {
-                self.walker.skip_current_dir();
-            }
+impl WalkCheckpoint {
+    /// The path of the last entry visited before the checkpoint, relative to the base directory.
+    ///
+    /// This is empty for the base directory itself.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
 
-            // The inner loop just advances the iterator until a match is found.
-            for entry in &mut self.walker {
-                match entry {
-                    Ok(e) => {
-                        let is_dir = e.file_type().is_dir();
+/// An iterator for recursively yielding glob matches.
+///
+/// The order of elements yielded by this iterator is unspecified.
+///
+/// Builders can be cloned in order to create several walkers with similar settings. The clones
+/// share the callbacks they were given, such as sorting functions and predicates.
+#[derive(Clone)]
+pub struct GlobWalkerBuilder {
+    root: PathBuf,
+    patterns: Vec<Pattern>,
+    walker: WalkDirConfig,
+    /// Depth bounds counted from the directory the patterns are anchored in.
+    min_depth_relative: Option<usize>,
+    max_depth_relative: Option<usize>,
+    traversal_order: TraversalOrder,
+    case_insensitive: bool,
+    auto_case_sensitivity: bool,
+    file_type: Option<FileType>,
+    /// The types enabled by `files_only` and the like, of which there may only be one.
+    only: FileType,
+    filename_encoding: FilenameEncoding,
+    allow_empty_base: bool,
+    require_base_exists: bool,
+    #[cfg(feature = "unicode-normalization")]
+    unicode_normalization: UnicodeNormalization,
+    track_symlink_targets: bool,
+    predicate: Option<EntryPredicate>,
+    benchmark_mode: bool,
+    resolve_dir: Option<PathBuf>,
+    thread_count: usize,
+    error_limit: Option<usize>,
+    max_entries: Option<usize>,
+    timeout: Option<Duration>,
+    matched_contents_first: bool,
+    auto_ignore: bool,
+    include_root: bool,
+    walk_root_entry: bool,
+    follow_links_for_dirs_only: bool,
+    #[cfg(unix)]
+    device: Option<u64>,
+    metadata_filters: MetadataFilters,
+    skip_hidden: bool,
+    gitignore: bool,
+    entry_filter: Option<EntryPredicate>,
+    detect_cycles: bool,
+    skip_dirs: Vec<String>,
+    max_dir_entries: Option<usize>,
+    on_entry: Option<EntryCallback>,
+    on_match: Option<EntryCallback>,
+    on_error: Option<ErrorHandler>,
+    resume_from: Option<PathBuf>,
+    max_link_depth: Option<usize>,
+    deny_symlinks: bool,
+    deduplicate_inodes: bool,
+}
 
-                        let file_type = if e.file_type().is_dir() {
-                            Some(FileType::DIR)
-                        } else if e.file_type().is_file() {
-                            Some(FileType::FILE)
-                        } else if e.file_type().is_symlink() {
-                            Some(FileType::SYMLINK)
-                        } else {
-                            None
-                        };
+type EntryPredicate = Arc<dyn Fn(&DirEntry) -> bool + Send + Sync + 'static>;
+type MissLogger = Arc<dyn Fn(&Path, SkipReason) + Send + Sync + 'static>;
+type EntryCallback = Arc<dyn Fn(&DirEntry) + Send + Sync + 'static>;
+type ErrorHandler = Arc<dyn Fn(WalkError) + Send + Sync + 'static>;
+/// The device and inode numbers of a set of files, shared between the walkers of a walk.
+#[cfg(unix)]
+type FileIds = Arc<std::sync::Mutex<HashSet<(u64, u64)>>>;
 
-                        let file_type_matches = match (self.file_type_filter.as_ref(), file_type) {
-                            (None, _) => true,
-                            (Some(_), None) => false,
-                            (Some(filter), Some(actual)) => filter.contains(actual),
-                        };
+/// The callbacks and the `walkdir` tweaks are left out, since they cannot be printed.
+impl std::fmt::Debug for GlobWalkerBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let patterns: Vec<_> = self.patterns.iter().map(|p| &p.pattern).collect();
+        f.debug_struct("GlobWalkerBuilder")
+            .field("root", &self.root)
+            .field("patterns", &patterns)
+            .field("min_depth", &self.walker.min_depth)
+            .field("max_depth", &self.walker.max_depth)
+            .field("follow_links", &self.walker.follow_links)
+            .field("contents_first", &self.walker.contents_first)
+            .field("same_file_system", &self.walker.same_file_system)
+            .field("traversal_order", &self.traversal_order)
+            .field("case_insensitive", &self.case_insensitive)
+            .field("auto_case_sensitivity", &self.auto_case_sensitivity)
+            .field("file_type", &self.file_type)
+            .field("allow_empty_base", &self.allow_empty_base)
+            .field("require_base_exists", &self.require_base_exists)
+            .field("track_symlink_targets", &self.track_symlink_targets)
+            .field("benchmark_mode", &self.benchmark_mode)
+            .field("matched_contents_first", &self.matched_contents_first)
+            .field("auto_ignore", &self.auto_ignore)
+            .field("include_root", &self.include_root)
+            .field("walk_root_entry", &self.walk_root_entry)
+            .field(
+                "follow_links_for_dirs_only",
+                &self.follow_links_for_dirs_only,
+            )
+            .field("deny_symlinks", &self.deny_symlinks)
+            .field("skip_hidden", &self.skip_hidden)
+            .field("gitignore", &self.gitignore)
+            .field("detect_cycles", &self.detect_cycles)
+            .field("deduplicate_inodes", &self.deduplicate_inodes)
+            .field("skip_dirs", &self.skip_dirs)
+            .finish_non_exhaustive()
+    }
+}
 
-                        // Strip the common base directory so that the matcher will be
-                        // able to recognize the file name.
-                        // `unwrap` here is safe, since walkdir returns the files with relation
-                        // to the given base-dir.
-                        let path = e.path().strip_prefix(self.ignore.path()).unwrap();
+fn cmp_file_size(a: &DirEntry, b: &DirEntry) -> Ordering {
+    match (a.metadata(), b.metadata()) {
+        (Ok(a), Ok(b)) => a.len().cmp(&b.len()),
+        _ => Ordering::Equal,
+    }
+}
 
-                        // The path might be empty after stripping if the current base-directory is matched.
-                        if path.as_os_str().is_empty() {
-                            continue 'skipper;
-                        }
+fn cmp_modified_time(a: &DirEntry, b: &DirEntry) -> Ordering {
+    let modified = |e: &DirEntry| e.metadata().ok().and_then(|m| m.modified().ok());
+    match (modified(a), modified(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => Ordering::Equal,
+    }
+}
 
-                        match self.ignore.matched(path, is_dir) {
-                            Match::Whitelist(_) if file_type_matches => return Some(Ok(e)),
-                            // If the directory is ignored, quit the iterator loop and
-                            // skip-out of this directory.
-                            Match::Ignore(_) if is_dir => {
-                                skip_dir = true;
-                                continue 'skipper;
-                            }
-                            _ => {}
-                        }
-                    }
-                    Err(e) => {
-                        return Some(Err(e));
-                    }
+/// Split a list of patterns on `:` and `,`, except within braces or brackets, or when escaped,
+/// for [`GlobWalkerBuilder::from_env_patterns`].
+///
+/// [`GlobWalkerBuilder::from_env_patterns`]: struct.GlobWalkerBuilder.html#method.from_env_patterns
+fn split_pattern_list(value: &str) -> Vec<&str> {
+    let mut patterns = Vec::new();
+    let mut start = 0;
+    let mut braces = 0usize;
+    let mut in_class = false;
+    let mut chars = value.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            ']' if in_class => in_class = false,
+            _ if in_class => {}
+            '[' => {
+                in_class = true;
+                // A `]` right after the opening bracket is part of the class.
+                let rest = &value[i + 1..];
+                let class = rest.strip_prefix('!').unwrap_or(rest);
+                if class.starts_with(']') {
+                    chars.nth(rest.len() - class.len());
                 }
             }
-            break;
+            '{' => braces += 1,
+            '}' => braces = braces.saturating_sub(1),
+            ':' | ',' if braces == 0 => {
+                patterns.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
         }
+    }
+    patterns.push(&value[start..]);
+    patterns
+}
 
-        None
+/// Read patterns from `reader`, one per line, skipping blank lines and comments.
+fn read_patterns<R: BufRead>(reader: R) -> Result<Vec<String>, GlobError> {
+    let mut patterns = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            patterns.push(line.to_owned());
+        }
     }
+    Ok(patterns)
 }
 
-/// Construct a new `GlobWalkerBuilder` with a glob pattern.
+/// The name of the file read by [`GlobWalkerBuilder::auto_ignore`].
 ///
-/// When iterated, the current directory will be recursively searched for paths
-/// matching `pattern`, unless the pattern specifies an absolute path.
-pub fn glob_builder<S: AsRef<str>>(pattern: S) -> GlobWalkerBuilder {
-    // Check to see if the pattern starts with an absolute path
-    let path_pattern: PathBuf = pattern.as_ref().into();
-    if path_pattern.is_absolute() {
-        // If the pattern is an absolute path, split it into the longest base and a pattern.
-        let mut base = PathBuf::new();
-        let mut pattern = PathBuf::new();
-        let mut globbing = false;
+/// [`GlobWalkerBuilder::auto_ignore`]: struct.GlobWalkerBuilder.html#method.auto_ignore
+const AUTO_IGNORE_FILE: &str = ".globwalkignore";
 
-        // All `to_str().unwrap()` calls should be valid since the input is a string.
-        for c in path_pattern.components() {
-            let os = c.as_os_str().to_str().unwrap();
-            for c in &["*", "{", "}"][..] {
-                if os.contains(c) {
-                    globbing = true;
-                    break;
-                }
-            }
+/// Bounds on the times and sizes, and the ownership and permissions, of the yielded entries.
+#[derive(Clone, Copy, Debug, Default)]
+struct MetadataFilters {
+    modified_after: Option<SystemTime>,
+    modified_before: Option<SystemTime>,
+    created_after: Option<SystemTime>,
+    created_before: Option<SystemTime>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    /// Whether directories are subject to the size bounds, rather than exempt from them.
+    size_of_dirs: bool,
+    #[cfg(unix)]
+    uid: Option<u32>,
+    #[cfg(unix)]
+    gid: Option<u32>,
+    /// The bits of the mode to check, and the values they must have.
+    #[cfg(unix)]
+    mode_mask: Option<(u32, u32)>,
+}
 
-            if globbing {
-                pattern.push(c);
-            } else {
-                base.push(c);
+impl MetadataFilters {
+    /// Check `entry` against the filters, returning the reason it was rejected, if it was.
+    fn check(&self, entry: &DirEntry) -> Result<Option<&'static str>, WalkError> {
+        fn in_range<T: PartialOrd>(value: T, after: Option<T>, before: Option<T>) -> bool {
+            after.map_or(true, |after| value > after)
+                && before.map_or(true, |before| value < before)
+        }
+
+        let modified = self.modified_after.is_some() || self.modified_before.is_some();
+        let created = self.created_after.is_some() || self.created_before.is_some();
+        let sized = (self.min_size.is_some() || self.max_size.is_some())
+            && (self.size_of_dirs || !entry.file_type().is_dir());
+        #[cfg(unix)]
+        let owned = self.uid.is_some() || self.gid.is_some() || self.mode_mask.is_some();
+        #[cfg(not(unix))]
+        let owned = false;
+        if !modified && !created && !sized && !owned {
+            return Ok(None);
+        }
+
+        let metadata = entry.metadata()?;
+        if modified {
+            let time = metadata.modified().map_err(|e| WalkError::io(entry, e))?;
+            if !in_range(time, self.modified_after, self.modified_before) {
+                return Ok(Some("outside of the time range"));
+            }
+        }
+        if created {
+            let time = metadata.created().map_err(|e| WalkError::io(entry, e))?;
+            if !in_range(time, self.created_after, self.created_before) {
+                return Ok(Some("outside of the time range"));
+            }
+        }
+        if sized {
+            let len = metadata.len();
+            if self.min_size.is_some_and(|min| len < min)
+                || self.max_size.is_some_and(|max| len > max)
+            {
+                return Ok(Some("outside of the size range"));
             }
         }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
 
-        let pat = pattern.to_str().unwrap();
-        if cfg!(windows) {
-            GlobWalkerBuilder::new(base.to_str().unwrap(), pat.replace('\\', "/"))
-        } else {
-            GlobWalkerBuilder::new(base.to_str().unwrap(), pat)
+            if self.uid.is_some_and(|uid| metadata.uid() != uid) {
+                return Ok(Some("not owned by the user"));
+            }
+            if self.gid.is_some_and(|gid| metadata.gid() != gid) {
+                return Ok(Some("not owned by the group"));
+            }
+            if let Some((mask, expected)) = self.mode_mask {
+                if metadata.mode() & mask != expected & mask {
+                    return Ok(Some("mode does not match"));
+                }
+            }
         }
-    } else {
-        // If the pattern is relative, start searching from the current directory.
-        GlobWalkerBuilder::new(".", pattern)
+        Ok(None)
     }
 }
 
-/// Construct a new `GlobWalker` with a glob pattern.
-///
-/// When iterated, the current directory will be recursively searched for paths
-/// matching `pattern`, unless the pattern specifies an absolute path.
-pub fn glob<S: AsRef<str>>(pattern: S) -> Result<GlobWalker, GlobError> {
-    glob_builder(pattern).build()
-}
+impl GlobWalkerBuilder {
+    /// Construct a new `GlobWalker` with a glob pattern.
+    ///
+    /// When iterated, the `base` directory will be recursively searched for paths
+    /// matching `pattern`.
+    pub fn new<P, S>(base: P, pattern: S) -> Self
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        GlobWalkerBuilder::from_patterns(base, &[pattern])
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::{create_dir_all, File};
-    use tempfile::TempDir;
+    /// Construct a new `GlobWalker` from plain configuration data.
+    ///
+    /// The other settings take their default values, and can still be changed on the returned
+    /// builder.
+    pub fn from_config(config: GlobWalkerConfig) -> Self {
+        let mut builder = GlobWalkerBuilder::from_patterns(&config.root, &config.patterns)
+            .max_depth(config.max_depth)
+            .min_depth(config.min_depth)
+            .follow_links(config.follow_links)
+            .case_insensitive(config.case_insensitive);
+        builder.walker.max_open = config.max_open;
+        builder
+    }
 
-    fn touch(dir: &TempDir, names: &[&str]) {
-        for name in names {
-            let name = normalize_path_sep(name);
-            File::create(dir.path().join(name)).expect("Failed to create a test file");
+    /// The settings of this builder that can be expressed as plain data.
+    ///
+    /// Everything else is left out, notably the callbacks (such as [`sort_by`] and the predicate
+    /// of [`from_find_expression`]), which cannot be serialized, as well as pattern groups and
+    /// priorities; only the patterns themselves are kept, in order of priority.
+    ///
+    /// [`sort_by`]: #method.sort_by
+    /// [`from_find_expression`]: #method.from_find_expression
+    pub fn to_config(&self) -> GlobWalkerConfig {
+        let mut patterns: Vec<_> = self.patterns.iter().collect();
+        patterns.sort_by_key(|p| p.priority);
+        GlobWalkerConfig {
+            root: self.root.clone(),
+            patterns: patterns.into_iter().map(|p| p.pattern.clone()).collect(),
+            min_depth: self.walker.min_depth,
+            max_depth: self.walker.max_depth,
+            follow_links: self.walker.follow_links,
+            max_open: self.walker.max_open,
+            case_insensitive: self.case_insensitive,
         }
     }
 
-    fn normalize_path_sep<S: AsRef<str>>(s: S) -> String {
-        s.as_ref()
-            .replace("[/]", if cfg!(windows) { "\\" } else { "/" })
+    /// Construct a builder for walking several base directories with the same patterns.
+    ///
+    /// Each base directory gets its own matcher, so the patterns are relative to every base in
+    /// turn, just as if a separate walker was built for each one. Settings can be applied to all
+    /// the walkers through [`GlobWalkerMultiBuilder::configure`].
+    ///
+    /// [`GlobWalkerMultiBuilder::configure`]: struct.GlobWalkerMultiBuilder.html#method.configure
+    pub fn from_patterns_multi_base<P, S>(bases: &[P], patterns: &[S]) -> GlobWalkerMultiBuilder
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        GlobWalkerMultiBuilder {
+            builders: bases
+                .iter()
+                .map(|base| GlobWalkerBuilder::from_patterns(base, patterns))
+                .collect(),
+        }
     }
 
-    fn equate_to_expected(g: GlobWalker, mut expected: Vec<String>, dir_path: &Path) {
-        for matched_file in g.into_iter().filter_map(Result::ok) {
-            let path = matched_file
-                .path()
-                .strip_prefix(dir_path)
-                .unwrap()
-                .to_str()
-                .unwrap();
-            let path = normalize_path_sep(path);
+    /// Construct a new `GlobWalker` from a list of patterns.
+    ///
+    /// When iterated, the `base` directory will be recursively searched for paths
+    /// matching `patterns`.
+    pub fn from_patterns<P, S>(base: P, patterns: &[S]) -> Self
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        GlobWalkerBuilder {
+            root: base.as_ref().into(),
+            patterns: patterns
+                .iter()
+                .map(|p| Pattern::new(p, None))
+                .collect::<_>(),
+            walker: WalkDirConfig::default(),
+            min_depth_relative: None,
+            max_depth_relative: None,
+            traversal_order: TraversalOrder::default(),
+            case_insensitive: false,
+            auto_case_sensitivity: false,
+            file_type: None,
+            only: FileType::empty(),
+            filename_encoding: FilenameEncoding::default(),
+            allow_empty_base: false,
+            require_base_exists: false,
+            #[cfg(feature = "unicode-normalization")]
+            unicode_normalization: UnicodeNormalization::default(),
+            track_symlink_targets: false,
+            predicate: None,
+            benchmark_mode: false,
+            resolve_dir: None,
+            thread_count: 1,
+            error_limit: None,
+            max_entries: None,
+            timeout: None,
+            matched_contents_first: false,
+            auto_ignore: false,
+            include_root: false,
+            walk_root_entry: false,
+            follow_links_for_dirs_only: false,
+            #[cfg(unix)]
+            device: None,
+            metadata_filters: MetadataFilters::default(),
+            skip_hidden: false,
+            gitignore: false,
+            entry_filter: None,
+            detect_cycles: false,
+            skip_dirs: Vec::new(),
+            max_dir_entries: None,
+            on_entry: None,
+            on_match: None,
+            on_error: None,
+            resume_from: None,
+            max_link_depth: None,
+            deny_symlinks: false,
+            deduplicate_inodes: false,
+        }
+    }
+
+    /// Construct a new `GlobWalker` from a list of `(pattern, tag)` pairs.
+    ///
+    /// Each pattern is added to the pattern group named by its tag, as if by
+    /// [`add_pattern_group`], so that [`GlobWalker::with_match_info`] reports the tag of the
+    /// pattern that matched each entry.
+    ///
+    /// [`add_pattern_group`]: #method.add_pattern_group
+    /// [`GlobWalker::with_match_info`]: struct.GlobWalker.html#method.with_match_info
+    pub fn from_pairs<P, S, T>(base: P, pairs: &[(S, T)]) -> Self
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        let mut builder = GlobWalkerBuilder::from_patterns::<_, &str>(base, &[]);
+        builder.patterns = pairs
+            .iter()
+            .map(|(pattern, tag)| Pattern::new(pattern, Some(tag.as_ref())))
+            .collect();
+        builder
+    }
+
+    /// Construct a new `GlobWalker` from a `find(1)` expression.
+    ///
+    /// The following subset of `find`'s expression language is supported:
+    /// `-name`, `-iname`, `-type f|d|l`, `-maxdepth`, `-mindepth`, `-newer FILE`,
+    /// `-size [+|-]N[cwbkMG]`, `-not` (or `!`), `-and` (or `-a`), `-or` (or `-o`), and
+    /// parentheses. Arguments may be quoted with single or double quotes.
+    ///
+    /// Like `find`, every entry under `base` is considered, and only those satisfying the
+    /// expression are yielded. Unlike `find`, the base directory itself is never yielded.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["a.rs", "b.toml", "src/c.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let walker = globwalk::GlobWalkerBuilder::from_find_expression(
+    ///     BASE_DIR,
+    ///     "-maxdepth 1 -type f ( -name '*.rs' -o -name '*.toml' )",
+    /// )?
+    /// .build()?;
+    /// assert_eq!(walker.count(), 2);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    pub fn from_find_expression<P>(base: P, find_args: &str) -> Result<Self, ParseError>
+    where
+        P: AsRef<Path>,
+    {
+        let find = find::parse(find_args)?;
+        let mut builder = GlobWalkerBuilder::new(base, "**");
+        if let Some(depth) = find.min_depth {
+            builder = builder.min_depth(depth);
+        }
+        if let Some(depth) = find.max_depth {
+            builder = builder.max_depth(depth);
+        }
+        let expr = find.expr;
+        builder.predicate = Some(Arc::new(move |e| expr.matches(e)));
+        Ok(builder)
+    }
+
+    /// Construct a new `GlobWalker` from patterns stored in environment variables.
+    ///
+    /// `include_var` and `exclude_var` hold lists of patterns separated by `:` or `,`. These are
+    /// not separators within braces or brackets, so `*.{rs,toml}` is kept as a single pattern,
+    /// nor when escaped with a backslash.
+    /// Patterns read from `exclude_var` are added as negations (prefixed with `!`).
+    ///
+    /// Missing variables are treated as empty; if no include patterns are given, every path under
+    /// `base` is matched.
+    /// An error is returned if one of the variables is not valid unicode.
+    pub fn from_env_patterns<P>(
+        base: P,
+        include_var: &str,
+        exclude_var: Option<&str>,
+    ) -> Result<Self, GlobError>
+    where
+        P: AsRef<Path>,
+    {
+        fn read_var(name: &str) -> Result<Vec<String>, GlobError> {
+            match std::env::var(name) {
+                Ok(value) => Ok(split_pattern_list(&value)
+                    .into_iter()
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect()),
+                Err(std::env::VarError::NotPresent) => Ok(Vec::new()),
+                Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()),
+            }
+        }
+
+        let mut patterns = read_var(include_var)?;
+        if patterns.is_empty() {
+            patterns.push(String::from("**"));
+        }
+        if let Some(exclude_var) = exclude_var {
+            patterns.extend(
+                read_var(exclude_var)?
+                    .into_iter()
+                    .map(|p| format!("!{}", p)),
+            );
+        }
+
+        Ok(GlobWalkerBuilder::from_patterns(base, &patterns))
+    }
+
+    /// Construct a new `GlobWalker` from the patterns stored in the environment variable
+    /// `var_name`.
+    ///
+    /// The patterns are separated like the entries of `PATH`: by `;` on Windows, and by `:`
+    /// elsewhere. Unlike [`from_env_patterns`], the patterns are taken as they are, so exclusions
+    /// must be written as negated patterns, and a missing variable results in a builder without
+    /// any pattern.
+    ///
+    /// An error is returned if the variable is not valid unicode, or if one of the patterns is
+    /// invalid.
+    ///
+    /// [`from_env_patterns`]: #method.from_env_patterns
+    pub fn from_env<P: AsRef<Path>>(base: P, var_name: &str) -> Result<Self, GlobError> {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        let patterns: Vec<_> = match std::env::var(var_name) {
+            Ok(value) => value
+                .split(separator)
+                .filter(|p| !p.is_empty())
+                .map(ToOwned::to_owned)
+                .collect(),
+            Err(std::env::VarError::NotPresent) => Vec::new(),
+            Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()),
+        };
+
+        let builder = GlobWalkerBuilder::from_patterns(base, &patterns);
+        builder.validate().map_err(|mut errors| errors.remove(0))?;
+        Ok(builder)
+    }
+
+    /// Construct a new `GlobWalker` from patterns read from `reader`, one per line.
+    ///
+    /// Surrounding whitespace is trimmed, and blank lines and lines starting with `#` are
+    /// ignored. Negated patterns are supported as usual.
+    ///
+    /// An error is returned if reading fails.
+    pub fn from_reader<P, R>(base: P, reader: R) -> Result<Self, GlobError>
+    where
+        P: AsRef<Path>,
+        R: BufRead,
+    {
+        let patterns = read_patterns(reader)?;
+        Ok(GlobWalkerBuilder::from_patterns(base, &patterns))
+    }
+
+    /// Construct one `GlobWalker` per directory matching `root_pattern`, each searching for
+    /// paths matching `pattern`.
+    ///
+    /// `root_pattern` is expanded with [`glob::glob`], so `"/projects/*/src"` produces a builder
+    /// for the `src` directory of every project. Matches that are not directories are ignored,
+    /// and the builders are returned in alphabetical order of their roots.
+    ///
+    /// An error is returned if `root_pattern` is invalid, or if a path could not be read while
+    /// expanding it.
+    ///
+    /// [`glob::glob`]: https://docs.rs/glob/0.3/glob/fn.glob.html
+    #[cfg(feature = "glob")]
+    pub fn new_with_root_pattern<S>(root_pattern: &str, pattern: S) -> Result<Vec<Self>, GlobError>
+    where
+        S: AsRef<str>,
+    {
+        let roots = glob::glob(root_pattern).map_err(|e| {
+            GlobError(ignore::Error::Glob {
+                glob: Some(root_pattern.to_owned()),
+                err: e.to_string(),
+            })
+        })?;
+
+        let mut builders = Vec::new();
+        for root in roots {
+            let root = root.map_err(std::io::Error::from)?;
+            if root.is_dir() {
+                builders.push(GlobWalkerBuilder::new(root, pattern.as_ref()));
+            }
+        }
+        Ok(builders)
+    }
+
+    /// Set the minimum depth of entries yielded by the iterator.
+    ///
+    /// The smallest depth is `0` and always corresponds to the path given
+    /// to the `new` function on this type. Its direct descendents have depth
+    /// `1`, and their descendents have depth `2`, and so on.
+    #[must_use = "builder methods must be chained"]
+    pub fn min_depth(mut self, depth: usize) -> Self {
+        self.walker.set_min_depth(depth);
+        self
+    }
+
+    /// Set the maximum depth of entries yield by the iterator.
+    ///
+    /// The smallest depth is `0` and always corresponds to the path given
+    /// to the `new` function on this type. Its direct descendents have depth
+    /// `1`, and their descendents have depth `2`, and so on.
+    ///
+    /// Note that this will not simply filter the entries of the iterator, but
+    /// it will actually avoid descending into directories when the depth is
+    /// exceeded.
+    #[must_use = "builder methods must be chained"]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.walker.set_max_depth(depth);
+        self
+    }
+
+    /// Set the minimum depth of entries yielded by the iterator, counted from the directory the
+    /// patterns are anchored in.
+    ///
+    /// The anchor of a pattern is the directory named by its leading components without
+    /// wildcards: `src/bin/**/*.rs` is anchored in `src/bin`, at depth `2`, so its direct
+    /// descendents are at relative depth `1`. Patterns without a slash other than a trailing one
+    /// match at any depth, and are anchored in the base directory. With several patterns, the
+    /// shallowest anchor of the patterns that are not negated is used.
+    ///
+    /// This applies on top of [`min_depth`](#method.min_depth): an entry must satisfy both.
+    #[must_use = "builder methods must be chained"]
+    pub fn min_depth_relative(mut self, depth: usize) -> Self {
+        self.min_depth_relative = Some(depth);
+        self
+    }
+
+    /// Set the maximum depth of entries yielded by the iterator, counted from the directory the
+    /// patterns are anchored in.
+    ///
+    /// See [`min_depth_relative`](#method.min_depth_relative) for how the anchor is determined.
+    /// This applies on top of [`max_depth`](#method.max_depth), and likewise avoids descending
+    /// into directories when the depth is exceeded.
+    #[must_use = "builder methods must be chained"]
+    pub fn max_depth_relative(mut self, depth: usize) -> Self {
+        self.max_depth_relative = Some(depth);
+        self
+    }
+
+    /// Follow symbolic links. By default, this is disabled.
+    ///
+    /// When `yes` is `true`, symbolic links are followed as if they were
+    /// normal directories and files. If a symbolic link is broken or is
+    /// involved in a loop, an error is yielded.
+    ///
+    /// When enabled, the yielded [`DirEntry`] values represent the target of
+    /// the link while the path corresponds to the link. See the [`DirEntry`]
+    /// type for more details.
+    ///
+    /// [`DirEntry`]: struct.DirEntry.html
+    #[must_use = "builder methods must be chained"]
+    pub fn follow_links(mut self, yes: bool) -> Self {
+        self.walker.follow_links = yes;
+        self
+    }
+
+    /// Follow symbolic links to directories, but do not yield symbolic links to anything else.
+    ///
+    /// When `yes` is `true`, this enables [`follow_links`], so that linked directories are
+    /// descended into, and skips the entries that are symbolic links to files (or to other
+    /// non-directories). This is useful when directory links are trusted, e.g. as workspace links
+    /// or mount points, but file links are not.
+    ///
+    /// Setting this to `false` only disables the skipping; links are still followed if
+    /// [`follow_links`] is enabled.
+    ///
+    /// [`follow_links`]: #method.follow_links
+    #[must_use = "builder methods must be chained"]
+    pub fn follow_links_for_dirs_only(mut self, yes: bool) -> Self {
+        if yes {
+            self.walker.follow_links = true;
+        }
+        self.follow_links_for_dirs_only = yes;
+        self
+    }
+
+    /// Fail the walk when a symbolic link is encountered. By default, this is disabled.
+    ///
+    /// When `yes` is `true`, the first symbolic link visited below the base directory, whether it
+    /// matches the patterns or not, makes the walker yield an error of kind `InvalidInput`, after
+    /// which the walk stops, on all threads if [`with_thread_count`] is used. This is meant for
+    /// tools that must not process a tree containing links at all, whereas links are otherwise
+    /// either yielded as links or followed, depending on [`follow_links`]. Links in excluded
+    /// directories, which are not descended into, are not visited.
+    ///
+    /// [`follow_links`]: #method.follow_links
+    /// [`with_thread_count`]: #method.with_thread_count
+    #[must_use = "builder methods must be chained"]
+    pub fn deny_symlinks(mut self, yes: bool) -> Self {
+        self.deny_symlinks = yes;
+        self
+    }
+
+    /// Follow symbolic links, but only descend into `n` levels of linked directories.
+    ///
+    /// This enables [`follow_links`]. A linked directory whose path goes through `n` other
+    /// linked directories is still yielded if it matches, but its contents are not visited, so
+    /// `0` keeps the walk from descending into any linked directory. Unlike the loops detected by
+    /// `walkdir`, this also bounds chains of links that do not point back to an ancestor.
+    ///
+    /// Directories can only be pruned before their contents are visited, so with
+    /// [`contents_first`](#method.contents_first), links are followed without a limit.
+    ///
+    /// [`follow_links`]: #method.follow_links
+    #[must_use = "builder methods must be chained"]
+    pub fn follow_links_max_depth(mut self, n: usize) -> Self {
+        self.walker.follow_links = true;
+        self.max_link_depth = Some(n);
+        self
+    }
+
+    /// Visit every directory at most once, identifying directories by their device and inode
+    /// numbers.
+    ///
+    /// `walkdir` already reports a link to one of the ancestors of an entry as an error when
+    /// following links, but a directory can still be reached through several paths, e.g. when
+    /// several links point to it. When `yes` is `true`, a directory that was already visited is
+    /// skipped along with its contents, so every file is yielded only once, even when the walk is
+    /// split between threads. Directories for which the identity cannot be read yield an error
+    /// instead.
+    ///
+    /// When directories are visited after their contents (see [`contents_first`]), the contents of
+    /// a directory are yielded before it is recognized as a duplicate.
+    ///
+    /// Inode numbers are only available on Unix; on other platforms this has no effect. This is
+    /// disabled by default.
+    ///
+    /// [`contents_first`]: #method.contents_first
+    #[must_use = "builder methods must be chained"]
+    pub fn detect_cycles(mut self, yes: bool) -> Self {
+        self.detect_cycles = yes;
+        self
+    }
+
+    /// Yield every file at most once, identifying files by their device and inode numbers.
+    ///
+    /// When `yes` is `true`, a matched entry that is the same file as an entry yielded earlier,
+    /// such as another hard link to it, is skipped; only the first path found is yielded. Which
+    /// path that is depends on the order of the walk. Entries for which the identity cannot be
+    /// read yield an error instead. Unlike [`detect_cycles`], this applies to every matched
+    /// entry, and does not prune directories. The identities of the yielded files are kept in
+    /// memory for the duration of the walk.
+    ///
+    /// Inode numbers are only available on Unix; on other platforms this has no effect. This is
+    /// disabled by default.
+    ///
+    /// [`detect_cycles`]: #method.detect_cycles
+    #[must_use = "builder methods must be chained"]
+    pub fn deduplicate_inodes(mut self, yes: bool) -> Self {
+        self.deduplicate_inodes = yes;
+        self
+    }
+
+    /// Do not descend into directories on other file systems than the base directory. By
+    /// default, this is disabled.
+    ///
+    /// When `yes` is `true`, directories that are the mount points of other file systems are
+    /// still yielded if they match, but their contents are not visited, so that a walk from `/`
+    /// does not cross into network shares or virtual file systems such as `/proc`. This is
+    /// `WalkDir::same_file_system`, which compares device numbers on Unix and volume serial
+    /// numbers on Windows. On other platforms, the walk yields an error instead.
+    #[must_use = "builder methods must be chained"]
+    pub fn same_file_system(mut self, yes: bool) -> Self {
+        self.walker.same_file_system = yes;
+        self
+    }
+
+    /// Only walk the directories that are on the device identified by `device_id`.
+    ///
+    /// Directories on other devices, such as the mount points of other file systems, are skipped
+    /// along with their contents. If the base directory itself is on another device, nothing is
+    /// yielded. This is a more targeted version of [`same_file_system`], for when the device of
+    /// interest is not the one of the base directory.
+    ///
+    /// The identifier of the device containing a path can be obtained with [`device_id_of`].
+    ///
+    /// [`same_file_system`]: #method.same_file_system
+    /// [`device_id_of`]: fn.device_id_of.html
+    #[cfg(unix)]
+    #[must_use = "builder methods must be chained"]
+    pub fn limit_to_device(mut self, device_id: u64) -> Self {
+        self.device = Some(device_id);
+        self
+    }
+
+    /// Skip the entries whose names start with a `.`, which are conventionally hidden.
+    ///
+    /// Hidden directories are not descended into, so this is a cheaper way of excluding them
+    /// than negated patterns such as `!.git`. The base directory itself is never skipped. This is
+    /// disabled by default.
+    #[must_use = "builder methods must be chained"]
+    pub fn skip_hidden(mut self, yes: bool) -> Self {
+        self.skip_hidden = yes;
+        self
+    }
+
+    /// Skip the entries listed in the `.gitignore` files of the walked directories.
+    ///
+    /// The `.gitignore` file of every directory, from the base directory down, applies to the
+    /// entries below it, with the same precedence rules as in git: deeper files override
+    /// shallower ones, and ignored directories are not descended into. The patterns of the walker
+    /// still apply on top of that, so an entry is only yielded if it matches the patterns and is
+    /// not ignored. `.gitignore` files above the base directory, `.git/info/exclude` and the
+    /// global git configuration are not consulted. This is disabled by default.
+    #[must_use = "builder methods must be chained"]
+    pub fn with_gitignore(mut self, yes: bool) -> Self {
+        self.gitignore = yes;
+        self
+    }
+
+    /// Read additional patterns from a `.globwalkignore` file in the base directory, if there is
+    /// one.
+    ///
+    /// The file is read when the walker is built, in the same format as [`from_reader`]. Its
+    /// patterns take precedence over all the other patterns, so that its negated patterns always
+    /// exclude the paths they match, and the file itself is never yielded. This is disabled by
+    /// default.
+    ///
+    /// [`from_reader`]: #method.from_reader
+    #[must_use = "builder methods must be chained"]
+    pub fn auto_ignore(mut self, yes: bool) -> Self {
+        self.auto_ignore = yes;
+        self
+    }
+
+    /// Skip the entries for which `filter` returns `false`, without descending into such
+    /// directories.
+    ///
+    /// Unlike the patterns, `filter` is called on every visited entry below the base directory,
+    /// before it is matched, so it can prune whole subtrees that the patterns would otherwise have
+    /// to visit entry by entry. Setting a new filter replaces the previous one.
+    #[must_use = "builder methods must be chained"]
+    pub fn filter_entry<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&DirEntry) -> bool + Send + Sync + 'static,
+    {
+        self.entry_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Call `f` for every entry visited by the walk, whether it is matched or not.
+    ///
+    /// This is meant for reporting the progress of long walks. `f` is called before the entry is
+    /// matched against the patterns, so it is also called for the directories that are then
+    /// skipped, but not for their contents. Errors are not reported. In multi-threaded walks, `f`
+    /// is called from the threads doing the walk. Setting a new callback replaces the previous
+    /// one.
+    #[must_use = "builder methods must be chained"]
+    pub fn on_entry<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DirEntry) + Send + Sync + 'static,
+    {
+        self.on_entry = Some(Arc::new(f));
+        self
+    }
+
+    /// Call `f` for every entry yielded by the walker, as it is yielded.
+    ///
+    /// See [`on_entry`] for a callback that sees every visited entry. Setting a new callback
+    /// replaces the previous one.
+    ///
+    /// [`on_entry`]: #method.on_entry
+    #[must_use = "builder methods must be chained"]
+    pub fn on_match<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DirEntry) + Send + Sync + 'static,
+    {
+        self.on_match = Some(Arc::new(f));
+        self
+    }
+
+    /// Pass the errors encountered during the walk to `f`, instead of yielding them.
+    ///
+    /// The walker then only yields `Ok` items, and keeps going after errors like it does when they
+    /// are yielded. This also applies to the final error of [`with_error_limit`], after which the
+    /// walk stops. Setting a new handler replaces the previous one.
+    ///
+    /// [`with_error_limit`]: #method.with_error_limit
+    #[must_use = "builder methods must be chained"]
+    pub fn on_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(WalkError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(f));
+        self
+    }
+
+    /// Skip the directories matching `pattern`, along with their contents.
+    ///
+    /// `pattern` has the same syntax as the patterns of the walker, and is matched against the
+    /// paths of directories relative to the base directory, before the patterns of the walker.
+    /// Unlike a negated pattern, it only applies to directories, and the directories it matches
+    /// are never read. This can be called several times to skip the directories matching any of
+    /// the given patterns.
+    ///
+    /// ```rust
+    /// # use globwalk::{GlobError, GlobWalkerBuilder};
+    /// # fn run() -> Result<(), GlobError> {
+    /// let walker = GlobWalkerBuilder::new(".", "**/*.rs")
+    ///     .skip_dirs_matching("target")?
+    ///     .skip_dirs_matching(".git")?
+    ///     .build()?;
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    pub fn skip_dirs_matching<S: AsRef<str>>(mut self, pattern: S) -> Result<Self, GlobError> {
+        let pattern = pattern.as_ref();
+        GitignoreBuilder::new(&self.root)
+            .add_line(None, pattern)
+            .map_err(|e| pattern_error_with_context(pattern, e))?;
+        self.skip_dirs.push(pattern.to_owned());
+        Ok(self)
+    }
+
+    /// Skip the directories that directly contain more than `entry_count` entries, along with
+    /// their contents.
+    ///
+    /// This prunes large directories that are known not to contain anything of interest, such
+    /// as `node_modules`, without listing them by name. The entries of every directory below the
+    /// base directory are counted when it is reached, which costs an extra read of the directory;
+    /// the count stops as soon as the limit is exceeded. If a directory cannot be read, it is not
+    /// skipped, and the error is reported when the walk descends into it.
+    ///
+    /// Directories can only be pruned before their contents are visited, so this has no effect
+    /// with [`contents_first`](#method.contents_first).
+    #[must_use = "builder methods must be chained"]
+    pub fn skip_dirs_larger_than(mut self, entry_count: usize) -> Self {
+        self.max_dir_entries = Some(entry_count);
+        self
+    }
+
+    /// Set the maximum number of simultaneously open file descriptors used
+    /// by the iterator.
+    ///
+    /// `n` must be greater than or equal to `1`. If `n` is `0`, then it is set
+    /// to `1` automatically. If this is not set, then it defaults to some
+    /// reasonably low number.
+    ///
+    /// This setting has no impact on the results yielded by the iterator
+    /// (even when `n` is `1`). Instead, this setting represents a trade off
+    /// between scarce resources (file descriptors) and memory. Namely, when
+    /// the maximum number of file descriptors is reached and a new directory
+    /// needs to be opened to continue iteration, then a previous directory
+    /// handle is closed and has its unyielded entries stored in memory. In
+    /// practice, this is a satisfying trade off because it scales with respect
+    /// to the *depth* of your file tree. Therefore, low values (even `1`) are
+    /// acceptable.
+    ///
+    /// Note that this value does not impact the number of system calls made by
+    /// an exhausted iterator.
+    ///
+    /// # Platform behavior
+    ///
+    /// On Windows, if `follow_links` is enabled, then this limit is not
+    /// respected. In particular, the maximum number of file descriptors opened
+    /// is proportional to the depth of the directory tree traversed.
+    #[must_use = "builder methods must be chained"]
+    pub fn max_open(mut self, n: usize) -> Self {
+        self.walker.max_open = Some(n);
+        self
+    }
+
+    /// Set the maximum number of directories kept open at once, rejecting `0`.
+    ///
+    /// This is [`max_open`] with validation: each open directory holds a file descriptor, and
+    /// when the limit is reached, the oldest open directory is closed and its remaining entries
+    /// are read into memory instead. A low limit therefore trades file descriptors for memory,
+    /// in proportion to the number of entries of the directories being walked, and never
+    /// prevents the walk from completing. On systems with a tight limit on open files, a small
+    /// value such as `4` leaves room for the files the caller opens while walking.
+    ///
+    /// [`max_open`]: #method.max_open
+    pub fn with_max_open_dirs(self, n: usize) -> Result<Self, GlobError> {
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the maximum number of open directories must be at least 1",
+            )
+            .into());
+        }
+        Ok(self.max_open(n))
+    }
+
+    /// Set the maximum number of open directories to a quarter of the limit on open files of the
+    /// process.
+    ///
+    /// On Unix, this reads the soft limit of `RLIMIT_NOFILE`, leaving the rest of the file
+    /// descriptors to the caller. On other platforms, or if the limit cannot be read, the
+    /// maximum is set to `4`. See [`with_max_open_dirs`](#method.with_max_open_dirs).
+    #[must_use = "builder methods must be chained"]
+    pub fn with_fd_limit_from_system(self) -> Self {
+        #[cfg(unix)]
+        let limit = {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            // SAFETY: `limit` is a valid `rlimit` for `getrlimit` to write to.
+            if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0 {
+                Some(usize::try_from(limit.rlim_cur / 4).unwrap_or(usize::MAX))
+            } else {
+                None
+            }
+        };
+        #[cfg(not(unix))]
+        let limit = None;
+        self.max_open(limit.unwrap_or(4).max(1))
+    }
+
+    /// Set the number of threads used for reading directories and matching their entries.
+    ///
+    /// The default is `1`, in which case the whole walk is done by the thread iterating the
+    /// walker. With more threads, the entries of the base directory are split between the threads,
+    /// each of them walking and matching the subtrees it was assigned, while the iterating thread
+    /// takes its own share and receives the matches of the others. This speeds up walks of large
+    /// trees whose time is dominated by file system access, but only helps if the base directory
+    /// has several subdirectories to divide.
+    ///
+    /// This complements [`max_open`], which is applied to each thread separately.
+    ///
+    /// Entries are yielded in no particular order, so this setting is ignored when a sorting
+    /// function is set, when [`contents_first`] is enabled, or when traversing in
+    /// [`TraversalOrder::BreadthFirst`] order. A value of `0` is treated as `1`.
+    ///
+    /// [`max_open`]: #method.max_open
+    /// [`contents_first`]: #method.contents_first
+    /// [`TraversalOrder::BreadthFirst`]: enum.TraversalOrder.html#variant.BreadthFirst
+    #[must_use = "builder methods must be chained"]
+    pub fn with_thread_count(mut self, n: usize) -> Self {
+        self.thread_count = n;
+        self
+    }
+
+    /// Set a function for sorting directory entries.
+    ///
+    /// If a compare function is set, the resulting iterator will return all
+    /// paths in sorted order. The compare function will be called to compare
+    /// entries from the same directory.
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by<F>(mut self, cmp: F) -> Self
+    where
+        F: FnMut(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
+    {
+        self.walker.set_sort_by(cmp);
+        self
+    }
+
+    /// Sort the entries of each directory by file name.
+    ///
+    /// This is a shorthand for [`sort_by`] with a comparison of [`DirEntry::file_name`].
+    ///
+    /// [`sort_by`]: #method.sort_by
+    /// [`DirEntry::file_name`]: struct.DirEntry.html#method.file_name
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_file_name(self) -> Self {
+        self.sort_by(|a, b| a.file_name().cmp(b.file_name()))
+    }
+
+    /// Sort the entries of each directory by file name, in reverse order.
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_file_name_reversed(self) -> Self {
+        self.sort_by(|a, b| b.file_name().cmp(a.file_name()))
+    }
+
+    /// Sort the entries of each directory by file name, ignoring case.
+    ///
+    /// Names are compared after converting them to lowercase, so that e.g. `B.txt` comes between
+    /// `a.txt` and `c.txt`. Names that only differ by case are sorted by their original bytes.
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_name_case_insensitive(self) -> Self {
+        self.sort_by(|a, b| {
+            let lowercase = |e: &DirEntry| e.file_name().to_string_lossy().to_lowercase();
+            lowercase(a)
+                .cmp(&lowercase(b))
+                .then_with(|| a.file_name().cmp(b.file_name()))
+        })
+    }
+
+    /// Sort the entries of each directory by file name, in alphabetical order.
+    ///
+    /// Unlike [`sort_by_file_name`], which compares the bytes of the names, this uses the root
+    /// collation of the Unicode Collation Algorithm, so that e.g. `ä.txt` comes between `a.txt`
+    /// and `b.txt` rather than after `z.txt`. Names that are not valid Unicode are compared
+    /// lossily.
+    ///
+    /// [`sort_by_file_name`]: #method.sort_by_file_name
+    #[cfg(feature = "icu_collator")]
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_locale_name(self) -> Self {
+        use icu_collator::{Collator, CollatorOptions};
+
+        // The collation data of the root locale is compiled into `icu_collator`.
+        match Collator::try_new(&Default::default(), CollatorOptions::new()) {
+            Ok(collator) => self.sort_by(move |a, b| {
+                collator
+                    .compare(
+                        &a.file_name().to_string_lossy(),
+                        &b.file_name().to_string_lossy(),
+                    )
+                    .then_with(|| a.file_name().cmp(b.file_name()))
+            }),
+            Err(_) => self.sort_by_file_name(),
+        }
+    }
+
+    /// Sort the entries of each directory by size, smallest first.
+    ///
+    /// The metadata of every entry is read for the comparison; entries for which it cannot be
+    /// read compare as equal to any other entry.
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_file_size(self) -> Self {
+        self.sort_by(cmp_file_size)
+    }
+
+    /// Sort the entries of each directory by size, largest first.
+    ///
+    /// See [`sort_by_file_size`].
+    ///
+    /// [`sort_by_file_size`]: #method.sort_by_file_size
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_file_size_reversed(self) -> Self {
+        self.sort_by(|a, b| cmp_file_size(b, a))
+    }
+
+    /// Sort the entries of each directory by modification time, oldest first.
+    ///
+    /// The metadata of every entry is read for the comparison; entries for which it cannot be
+    /// read, or which have no modification time on the current platform, compare as equal to any
+    /// other entry.
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_modified_time(self) -> Self {
+        self.sort_by(cmp_modified_time)
+    }
+
+    /// Sort the entries of each directory by modification time, newest first.
+    ///
+    /// See [`sort_by_modified_time`].
+    ///
+    /// [`sort_by_modified_time`]: #method.sort_by_modified_time
+    #[must_use = "builder methods must be chained"]
+    pub fn sort_by_modified_time_reversed(self) -> Self {
+        self.sort_by(|a, b| cmp_modified_time(b, a))
+    }
+
+    /// Yield a directory's contents before the directory itself. By default,
+    /// this is disabled.
+    ///
+    /// When `yes` is `false` (as is the default), the directory is yielded
+    /// before its contents are read. This is useful when, e.g. you want to
+    /// skip processing of some directories.
+    ///
+    /// When `yes` is `true`, the iterator yields the contents of a directory
+    /// before yielding the directory itself. This is useful when, e.g. you
+    /// want to recursively delete a directory.
+    #[must_use = "builder methods must be chained"]
+    pub fn contents_first(mut self, yes: bool) -> Self {
+        self.walker.contents_first = yes;
+        self
+    }
+
+    /// Yield the matched contents of a directory before the directory itself, if it matched.
+    ///
+    /// Unlike [`contents_first`], the tree is still walked with directories first, so that
+    /// excluded directories are not descended into, and matched directories are held back until
+    /// all the matches below them were yielded. This makes the order of the matches safe for
+    /// deleting them one after the other. The directories held back are kept in memory; there are
+    /// at most as many as the depth of the tree.
+    ///
+    /// [`contents_first`]: #method.contents_first
+    #[must_use = "builder methods must be chained"]
+    pub fn matched_contents_first(mut self, yes: bool) -> Self {
+        self.matched_contents_first = yes;
+        self
+    }
+
+    /// Apply a custom configuration to the underlying `WalkDir`.
+    ///
+    /// This is an escape hatch for `walkdir` settings that are not exposed by this builder, such
+    /// as `WalkDir::follow_root_links`. Tweaks are applied in order, after all other settings, to
+    /// every `WalkDir` created for the walk.
+    ///
+    /// Since a breadth-first traversal creates a `WalkDir` for each level of the tree, `f` may be
+    /// called more than once.
+    #[must_use = "builder methods must be chained"]
+    pub fn with_walkdir_tweak<F>(mut self, f: F) -> Self
+    where
+        F: Fn(WalkDir) -> WalkDir + Send + Sync + 'static,
+    {
+        self.walker.tweaks.push(Arc::new(f));
+        self
+    }
+
+    /// Set the order in which the directory tree is traversed.
+    ///
+    /// The default is [`TraversalOrder::DepthFirst`].
+    ///
+    /// With [`TraversalOrder::BreadthFirst`], directories that are excluded by the patterns are
+    /// still never descended into, and `contents_first` has no effect.
+    ///
+    /// [`TraversalOrder::DepthFirst`]: enum.TraversalOrder.html#variant.DepthFirst
+    /// [`TraversalOrder::BreadthFirst`]: enum.TraversalOrder.html#variant.BreadthFirst
+    #[must_use = "builder methods must be chained"]
+    pub fn traversal_order(mut self, order: TraversalOrder) -> Self {
+        self.traversal_order = order;
+        self
+    }
+
+    /// Toggle whether the globs should be matched case insensitively or not.
+    ///
+    /// This is disabled by default.
+    #[must_use = "builder methods must be chained"]
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Match the globs case insensitively on the platforms whose file systems typically are.
+    ///
+    /// When `yes` is `true`, this enables [`case_insensitive`] on Windows and macOS, and disables
+    /// it elsewhere. The actual file system is not inspected, so this is only a heuristic: a
+    /// case-sensitive volume on macOS is still matched case insensitively. When `yes` is `false`,
+    /// the globs are matched case sensitively, as with `case_insensitive(false)`.
+    ///
+    /// [`case_insensitive`]: #method.case_insensitive
+    #[must_use = "builder methods must be chained"]
+    pub fn case_insensitive_per_platform(self, yes: bool) -> Self {
+        self.case_insensitive(yes && cfg!(any(windows, target_os = "macos")))
+    }
+
+    /// Match the globs case insensitively if the file system of the base directory is.
+    ///
+    /// When `yes` is `true`, [`build`] probes the base directory by looking up one of its
+    /// entries under a different case, and matches the globs case insensitively if that finds
+    /// it, or case sensitively otherwise, regardless of [`case_insensitive`]. This follows the
+    /// actual volume, e.g. a case-sensitive one on macOS or a case-folded directory on Linux,
+    /// unlike [`case_insensitive_per_platform`]. If the base directory cannot be read, or has no
+    /// entry whose name has a case, the setting of [`case_insensitive`] is used instead.
+    ///
+    /// This is disabled by default.
+    ///
+    /// [`build`]: #method.build
+    /// [`case_insensitive`]: #method.case_insensitive
+    /// [`case_insensitive_per_platform`]: #method.case_insensitive_per_platform
+    #[must_use = "builder methods must be chained"]
+    pub fn auto_case_sensitivity(mut self, yes: bool) -> Self {
+        self.auto_case_sensitivity = yes;
+        self
+    }
+
+    /// Toggle filtering by file type.
+    /// `FileType` can be an OR of several types.
+    ///
+    /// Unlike [`files_only`], [`dirs_only`] and [`symlinks_only`], which cannot be combined,
+    /// this accepts any combination of types:
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["a.txt", "dir/b.txt"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// use globwalk::FileType;
+    ///
+    /// let walker = globwalk::GlobWalkerBuilder::new(BASE_DIR, "**")
+    ///     .file_type(FileType::FILE | FileType::SYMLINK)
+    ///     .build()?;
+    /// assert_eq!(walker.count(), 2);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// Note that not all file-types can be whitelisted by this filter (e.g. char-devices, fifos, etc.)
+    ///
+    /// [`files_only`]: #method.files_only
+    /// [`dirs_only`]: #method.dirs_only
+    /// [`symlinks_only`]: #method.symlinks_only
+    #[must_use = "builder methods must be chained"]
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = Some(file_type);
+        self
+    }
+
+    /// Only yield regular files.
+    ///
+    /// This is a shorthand for filtering by [`FileType::FILE`], which cannot be combined with
+    /// [`dirs_only`] or [`symlinks_only`]: enabling more than one of them makes [`build`] fail.
+    /// If [`file_type`] is set as well, entries have to pass both filters.
+    ///
+    /// [`FileType::FILE`]: struct.FileType.html#associatedconstant.FILE
+    /// [`dirs_only`]: #method.dirs_only
+    /// [`symlinks_only`]: #method.symlinks_only
+    /// [`build`]: #method.build
+    /// [`file_type`]: #method.file_type
+    #[must_use = "builder methods must be chained"]
+    pub fn files_only(mut self, yes: bool) -> Self {
+        self.only.set(FileType::FILE, yes);
+        self
+    }
+
+    /// Only yield directories.
+    ///
+    /// See [`files_only`](#method.files_only).
+    #[must_use = "builder methods must be chained"]
+    pub fn dirs_only(mut self, yes: bool) -> Self {
+        self.only.set(FileType::DIR, yes);
+        self
+    }
+
+    /// Only yield symbolic links.
+    ///
+    /// Links are only seen as such when [`follow_links`] is disabled; otherwise, the type of
+    /// their target is used. See [`files_only`](#method.files_only).
+    ///
+    /// [`follow_links`]: #method.follow_links
+    #[must_use = "builder methods must be chained"]
+    pub fn symlinks_only(mut self, yes: bool) -> Self {
+        self.only.set(FileType::SYMLINK, yes);
+        self
+    }
+
+    /// Only yield the matched entries that are at least `bytes` long.
+    ///
+    /// The length is read from the metadata of the entries that match the patterns; an error is
+    /// yielded for the entries whose metadata cannot be read. Directories are exempt from the
+    /// size bounds, unless the walk is restricted to directories, with [`dirs_only`] or
+    /// [`file_type`].
+    ///
+    /// [`dirs_only`]: #method.dirs_only
+    /// [`file_type`]: #method.file_type
+    #[must_use = "builder methods must be chained"]
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.metadata_filters.min_size = Some(bytes);
+        self
+    }
+
+    /// Only yield the matched entries that are at most `bytes` long.
+    ///
+    /// See [`min_size`](#method.min_size).
+    #[must_use = "builder methods must be chained"]
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.metadata_filters.max_size = Some(bytes);
+        self
+    }
+
+    /// Only yield the matched entries owned by the user with the ID `uid`.
+    ///
+    /// The owner is read from the metadata of the entries that match the patterns, which, like
+    /// the other metadata, is that of the link itself for symbolic links that are not followed.
+    /// Ownership is a Unix concept; on other platforms this has no effect.
+    #[must_use = "builder methods must be chained"]
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    pub fn with_uid(mut self, uid: u32) -> Self {
+        #[cfg(unix)]
+        {
+            self.metadata_filters.uid = Some(uid);
+        }
+        #[cfg(not(unix))]
+        let _ = uid;
+        self
+    }
+
+    /// Only yield the matched entries owned by the group with the ID `gid`.
+    ///
+    /// See [`with_uid`](#method.with_uid).
+    #[must_use = "builder methods must be chained"]
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    pub fn with_gid(mut self, gid: u32) -> Self {
+        #[cfg(unix)]
+        {
+            self.metadata_filters.gid = Some(gid);
+        }
+        #[cfg(not(unix))]
+        let _ = gid;
+        self
+    }
+
+    /// Only yield the matched entries whose mode bits selected by `mask` are those of `expected`.
+    ///
+    /// The mode includes the file type bits as well as the permissions, so for example
+    /// `with_mode_mask(0o111, 0o111)` only yields the entries that are executable by everyone,
+    /// and `with_mode_mask(0o4000, 0o4000)` only yields set-user-ID files. Bits of `expected`
+    /// outside of `mask` are ignored. On platforms other than Unix this has no effect.
+    #[must_use = "builder methods must be chained"]
+    #[cfg_attr(not(unix), allow(unused_mut))]
+    pub fn with_mode_mask(mut self, mask: u32, expected: u32) -> Self {
+        #[cfg(unix)]
+        {
+            self.metadata_filters.mode_mask = Some((mask, expected));
+        }
+        #[cfg(not(unix))]
+        let _ = (mask, expected);
+        self
+    }
+
+    /// Only yield the matched entries that were last modified after `time`.
+    ///
+    /// The modification time is read from the metadata of the entries that match the patterns;
+    /// an error is yielded for the entries whose metadata cannot be read.
+    #[must_use = "builder methods must be chained"]
+    pub fn modified_after(mut self, time: SystemTime) -> Self {
+        self.metadata_filters.modified_after = Some(time);
+        self
+    }
+
+    /// Only yield the matched entries that were last modified before `time`.
+    ///
+    /// See [`modified_after`](#method.modified_after).
+    #[must_use = "builder methods must be chained"]
+    pub fn modified_before(mut self, time: SystemTime) -> Self {
+        self.metadata_filters.modified_before = Some(time);
+        self
+    }
+
+    /// Only yield the matched entries that were created after `time`.
+    ///
+    /// Not all platforms and file systems record creation times. Where they are not available,
+    /// an error is yielded for every matched entry, instead of skipping it silently.
+    #[must_use = "builder methods must be chained"]
+    pub fn created_after(mut self, time: SystemTime) -> Self {
+        self.metadata_filters.created_after = Some(time);
+        self
+    }
+
+    /// Only yield the matched entries that were created before `time`.
+    ///
+    /// See [`created_after`](#method.created_after).
+    #[must_use = "builder methods must be chained"]
+    pub fn created_before(mut self, time: SystemTime) -> Self {
+        self.metadata_filters.created_before = Some(time);
+        self
+    }
+
+    /// Set how entries with non-UTF-8 paths are handled.
+    ///
+    /// By default, paths are used as they are reported by the operating system.
+    /// See [`FilenameEncoding`] for the available strategies.
+    ///
+    /// [`FilenameEncoding`]: enum.FilenameEncoding.html
+    #[must_use = "builder methods must be chained"]
+    pub fn with_filename_encoding(mut self, enc: FilenameEncoding) -> Self {
+        self.filename_encoding = enc;
+        self
+    }
+
+    /// Normalize patterns and paths to the given unicode form before matching them.
+    ///
+    /// This is disabled by default. See [`UnicodeNormalization`] for details.
+    ///
+    /// [`UnicodeNormalization`]: enum.UnicodeNormalization.html
+    #[cfg(feature = "unicode-normalization")]
+    #[must_use = "builder methods must be chained"]
+    pub fn normalize_unicode(mut self, strategy: UnicodeNormalization) -> Self {
+        self.unicode_normalization = strategy;
+        self
+    }
+
+    /// Resolve the targets of matched symbolic links. By default, this is disabled.
+    ///
+    /// When `yes` is `true`, the iterator returned by [`GlobWalker::symlink_entries`] reads the
+    /// target of every symbolic link it yields. Otherwise, no target is reported.
+    ///
+    /// [`GlobWalker::symlink_entries`]: struct.GlobWalker.html#method.symlink_entries
+    #[must_use = "builder methods must be chained"]
+    pub fn track_symlink_targets(mut self, yes: bool) -> Self {
+        self.track_symlink_targets = yes;
+        self
+    }
+
+    /// Treat a missing base directory as an empty walk. By default, this is disabled.
+    ///
+    /// When `yes` is `false` (as is the default), iterating a walker whose base directory does not
+    /// exist yields an error. When `yes` is `true`, such a walker yields nothing instead, which is
+    /// convenient for directories that may not have been created yet (e.g. build outputs).
+    #[must_use = "builder methods must be chained"]
+    pub fn allow_empty_base(mut self, yes: bool) -> Self {
+        self.allow_empty_base = yes;
+        self
+    }
+
+    /// Check that the base directory exists when building the walker. By default, this is
+    /// disabled.
+    ///
+    /// When `yes` is `true`, building fails with an error of kind `NotFound` if the base
+    /// directory does not exist or is not a directory, rather than leaving it to the walk to
+    /// report. This takes precedence over [`allow_empty_base`](#method.allow_empty_base).
+    #[must_use = "builder methods must be chained"]
+    pub fn require_base_exists(mut self, yes: bool) -> Self {
+        self.require_base_exists = yes;
+        self
+    }
+
+    /// Disable every optional operation of the walk, for measuring its raw throughput.
+    ///
+    /// When `yes` is `true`, sorting is disabled, symbolic link targets are not tracked, and
+    /// filters that require the metadata of entries (such as [`min_size`], [`modified_after`], or
+    /// the `-size` and `-newer` predicates of [`from_find_expression`]) are not applied. Entries are
+    /// then only matched using their paths and file types, which `walkdir` obtains without an extra
+    /// `stat` call on most platforms.
+    ///
+    /// This is meant for profiling; since filters are dropped, the yielded entries may differ from
+    /// the ones yielded in a normal walk.
+    ///
+    /// [`min_size`]: #method.min_size
+    /// [`modified_after`]: #method.modified_after
+    /// [`from_find_expression`]: #method.from_find_expression
+    #[must_use = "builder methods must be chained"]
+    pub fn benchmark_mode(mut self, yes: bool) -> Self {
+        self.benchmark_mode = yes;
+        self
+    }
+
+    /// Resolve the patterns that start with `./` or `../` relative to `dir`.
+    ///
+    /// By default, patterns are always matched against paths relative to the base directory. When
+    /// `dir` is set, a pattern such as `../shared/*.rs` is instead taken to be relative to `dir`,
+    /// and is rewritten into a pattern anchored at the base directory before being compiled. This
+    /// is useful when patterns come from a project file that lives elsewhere than the base
+    /// directory.
+    ///
+    /// Paths are resolved lexically, without accessing the file system, so `dir` and the base
+    /// directory should either both be absolute or both be relative to the same directory.
+    /// Building fails if a pattern resolves to a path outside of the base directory. Other
+    /// patterns are unaffected.
+    #[must_use = "builder methods must be chained"]
+    pub fn resolve_patterns_relative_to<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.resolve_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Yield the base directory itself. By default, this is disabled.
+    ///
+    /// `walkdir` yields the base directory as the entry at depth `0`, but it is never matched
+    /// against the patterns, and is not yielded by default; this has always been the behavior of
+    /// globwalk, and enabling this option is the only way to change it. When `yes` is `true`, the
+    /// base directory is yielded regardless of the patterns, as long as the minimum depth is `0`
+    /// and it passes the other filters (such as [`file_type`]).
+    ///
+    /// [`file_type`]: #method.file_type
+    #[must_use = "builder methods must be chained"]
+    pub fn include_root(mut self, yes: bool) -> Self {
+        self.include_root = yes;
+        self
+    }
+
+    /// Match the name of the base directory against the patterns. By default, this is disabled.
+    ///
+    /// Unlike [`include_root`], which yields the base directory unconditionally, this yields it
+    /// only if its name matches the patterns, as if it were an entry of its own parent: walking
+    /// a directory named `mod` with the pattern `mod` yields the directory itself, at depth `0`.
+    /// The name of a base directory such as `.` is found by canonicalizing it. The minimum depth
+    /// and the other filters (such as [`file_type`]) still apply, and the contents of the base
+    /// directory are matched as usual either way.
+    ///
+    /// [`include_root`]: #method.include_root
+    /// [`file_type`]: #method.file_type
+    #[must_use = "builder methods must be chained"]
+    pub fn with_walk_root_entry(mut self, yes: bool) -> Self {
+        self.walk_root_entry = yes;
+        self
+    }
+
+    /// Resume a walk from a checkpoint taken with [`GlobWalker::checkpoint`].
+    ///
+    /// The entries up to and including the last one visited before the checkpoint are skipped,
+    /// without being yielded or passed to the callbacks. The walk is fast-forwarded by walking
+    /// the tree again, but, in a depth-first walk, without descending into the directories that
+    /// were finished before the checkpoint.
+    ///
+    /// This relies on the entries being visited in the same order as in the original walk, so
+    /// both walks should have the same settings and a sorting function, such as
+    /// [`sort_by_file_name`]; the order of the entries of a directory is otherwise unspecified.
+    /// If the entry of the checkpoint no longer exists, nothing is yielded. Resuming is not
+    /// supported by multi-threaded walks, for which building the walker fails.
+    ///
+    /// [`GlobWalker::checkpoint`]: struct.GlobWalker.html#method.checkpoint
+    /// [`sort_by_file_name`]: #method.sort_by_file_name
+    #[must_use = "builder methods must be chained"]
+    pub fn resume_from(mut self, checkpoint: WalkCheckpoint) -> Self {
+        self.resume_from = Some(checkpoint.path);
+        self
+    }
+
+    /// Stop the walk once more than `max_errors` errors were encountered.
+    ///
+    /// The first `max_errors` errors are yielded as usual. The next one is replaced by a final
+    /// error, for which [`WalkError::error_count`] reports the number of errors encountered, and
+    /// after which the walker yields nothing. This avoids a flood of errors when walking a
+    /// degraded file system. A limit of `0` fails on the first error.
+    ///
+    /// By default, there is no limit.
+    ///
+    /// [`WalkError::error_count`]: struct.WalkError.html#method.error_count
+    #[must_use = "builder methods must be chained"]
+    pub fn with_error_limit(mut self, max_errors: usize) -> Self {
+        self.error_limit = Some(max_errors);
+        self
+    }
+
+    /// Add a pattern, after the ones given so far.
+    ///
+    /// The pattern behaves exactly like the ones given at construction.
+    #[must_use = "builder methods must be chained"]
+    pub fn add_pattern<S: AsRef<str>>(mut self, pattern: S) -> Self {
+        self.patterns.push(Pattern::new(pattern, None));
+        self
+    }
+
+    /// Add several patterns, after the ones given so far.
+    ///
+    /// See [`add_pattern`](#method.add_pattern).
+    #[must_use = "builder methods must be chained"]
+    pub fn add_patterns<S: AsRef<str>>(mut self, patterns: &[S]) -> Self {
+        self.patterns
+            .extend(patterns.iter().map(|p| Pattern::new(p, None)));
+        self
+    }
+
+    /// Add the patterns of `other` after the ones of this builder.
+    ///
+    /// Both builders must have the same base directory, since patterns are relative to it;
+    /// otherwise an error is returned. The patterns of `other` keep their groups and priorities,
+    /// and the narrower of the two depth ranges is used: the larger minimum depth and the smaller
+    /// maximum depth. Every other setting, such as [`case_insensitive`], is taken from this
+    /// builder, and those of `other` are dropped.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs", "Cargo.toml", "README.md"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// use globwalk::GlobWalkerBuilder;
+    ///
+    /// let walker = GlobWalkerBuilder::new(BASE_DIR, "*.rs")
+    ///     .merge(GlobWalkerBuilder::new(BASE_DIR, "*.toml"))?
+    ///     .build()?;
+    /// assert_eq!(walker.count(), 2);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`case_insensitive`]: #method.case_insensitive
+    pub fn merge(mut self, other: GlobWalkerBuilder) -> Result<Self, GlobError> {
+        if self.root != other.root {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot merge walkers of different base directories: {} and {}",
+                    self.root.display(),
+                    other.root.display()
+                ),
+            )
+            .into());
+        }
+
+        self.patterns.extend(other.patterns);
+        self.walker.min_depth = self.walker.min_depth.max(other.walker.min_depth);
+        self.walker.max_depth = self.walker.max_depth.min(other.walker.max_depth);
+        Ok(self)
+    }
+
+    /// Stop the walk once `n` entries were yielded.
+    ///
+    /// Errors do not count towards the limit. This is cheaper than limiting the number of items
+    /// taken from the walker, since multi-threaded walks are stopped as soon as the limit is
+    /// reached. With a limit of `0`, nothing is yielded.
+    ///
+    /// By default, there is no limit.
+    #[must_use = "builder methods must be chained"]
+    pub fn max_entries(mut self, n: usize) -> Self {
+        self.max_entries = Some(n);
+        self
+    }
+
+    /// Stop the walk once `timeout` has elapsed since the walker was built.
+    ///
+    /// When the timeout is exceeded, the walker yields a final error, whose
+    /// [`io_error`](struct.WalkError.html#method.io_error) has the kind
+    /// `std::io::ErrorKind::TimedOut`, and then stops, on all threads if [`with_thread_count`]
+    /// is used. The time is checked after every entry or error read from the file system, so a
+    /// single system call that hangs is not interrupted; the walk is only stopped once it returns.
+    ///
+    /// By default, there is no timeout.
+    ///
+    /// [`with_thread_count`]: #method.with_thread_count
+    #[must_use = "builder methods must be chained"]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a named group of patterns.
+    ///
+    /// The patterns behave exactly like the ones given at construction, but entries they match
+    /// are attributed to the group `name` when iterating [`GlobWalker::with_match_info`].
+    ///
+    /// [`GlobWalker::with_match_info`]: struct.GlobWalker.html#method.with_match_info
+    #[must_use = "builder methods must be chained"]
+    pub fn add_pattern_group<S: AsRef<str>>(mut self, name: &str, patterns: &[S]) -> Self {
+        self.patterns
+            .extend(patterns.iter().map(|p| Pattern::new(p, Some(name))));
+        self
+    }
+
+    /// Add a pattern with an explicit priority.
+    ///
+    /// Patterns are normally evaluated in the order they were added, with later patterns taking
+    /// precedence over earlier ones. Before building, patterns are sorted by ascending priority
+    /// (keeping the order of patterns with equal priorities), so a pattern with a higher priority
+    /// overrides patterns with lower priorities regardless of the order they were added in.
+    ///
+    /// Patterns added in any other way have a priority of `0`.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["README.md", "main.rs", "build.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// // Matches `README.md` and `main.rs`, even though `**/*.rs` was added last.
+    /// let walker = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.md")
+    ///     .add_pattern_with_priority("!build.rs", 100)
+    ///     .add_pattern_with_priority("**/*.rs", 0)
+    ///     .build()?;
+    /// assert_eq!(walker.count(), 2);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    #[must_use = "builder methods must be chained"]
+    pub fn add_pattern_with_priority<S: AsRef<str>>(mut self, pattern: S, priority: i32) -> Self {
+        let mut pattern = Pattern::new(pattern, None);
+        pattern.priority = priority;
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Exclude the git submodules listed in the base directory's `.gitmodules` file.
+    ///
+    /// Every `path = ...` entry of a `[submodule "name"]` section is added as an anchored negation
+    /// pattern, so the submodules and their contents are skipped.
+    ///
+    /// If `.gitmodules` does not exist, this is a no-op.
+    pub fn exclude_git_submodules(mut self) -> Result<Self, GlobError> {
+        let contents = match std::fs::read_to_string(self.root.join(".gitmodules")) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(self),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut in_submodule = false;
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_submodule = line.starts_with("[submodule");
+                continue;
+            }
+            if !in_submodule {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "path" {
+                    let path = value.trim().trim_matches('"').trim_matches('/');
+                    if !path.is_empty() {
+                        self.patterns
+                            .push(Pattern::new(format!("!/{}", path), None));
+                    }
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Check all the patterns, reporting every invalid one rather than only the first.
+    ///
+    /// This does the same checks as [`build`] for the patterns, without consuming the builder or
+    /// reading the file system, e.g. to report all the mistakes in a configuration file at once.
+    ///
+    /// ```rust
+    /// let builder = globwalk::GlobWalkerBuilder::from_patterns(".", &["{a,b", "*.rs", "[z-a]"]);
+    /// assert_eq!(builder.validate().unwrap_err().len(), 2);
+    /// ```
+    ///
+    /// [`build`]: #method.build
+    pub fn validate(&self) -> Result<(), Vec<GlobError>> {
+        let mut builder = OverrideBuilder::new(&self.root);
+        let mut errors = Vec::new();
+        if let Err(e) = builder.case_insensitive(self.case_insensitive) {
+            errors.push(GlobError(e));
+        }
+
+        for pattern in &self.patterns {
+            let mut pattern = pattern.clone();
+            if let Some(dir) = &self.resolve_dir {
+                if let Err(e) = pattern.resolve_relative_to(dir, &self.root) {
+                    errors.push(e);
+                    continue;
+                }
+            }
+            #[cfg(feature = "unicode-normalization")]
+            {
+                pattern.pattern = self.unicode_normalization.normalize(&pattern.pattern);
+            }
+            if let Err(e) = builder.add(&pattern.pattern) {
+                errors.push(pattern_error_with_context(&pattern.pattern, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Finalize and build a `GlobWalker` instance.
+    pub fn build(self) -> Result<GlobWalker, GlobError> {
+        let mut walkers = self.build_walkers(None)?;
+        Ok(walkers.remove(0))
+    }
+
+    /// Build a `GlobWalker` and collect all the entries it yields.
+    ///
+    /// The walk stops at the first error, which is returned instead of the entries.
+    pub fn try_build_and_collect(self) -> Result<Vec<DirEntry>, GlobWalkError> {
+        Ok(self.build()?.collect::<Result<_, _>>()?)
+    }
+
+    /// Finalize and build a parallel iterator over the matches, which walks the tree on the
+    /// `rayon` thread pool.
+    ///
+    /// The entries of the base directory are split between as many walkers as the pool has
+    /// threads, as with [`with_thread_count`], and each walker reads and matches the subtrees
+    /// it was assigned on whichever thread is available. All the other settings are respected,
+    /// except that entries are produced in no particular order; a sorting function only affects
+    /// the order in which the entries of each directory are visited, and the traversal order is
+    /// ignored. The limits set by [`with_error_limit`] and [`max_entries`] apply to each walker
+    /// separately. As with [`with_thread_count`], a walk with [`contents_first`] enabled is not
+    /// split, and is done by a single walker.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs", "lib/a.rs", "lib/b.rs", "README.md"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// use rayon::iter::ParallelIterator;
+    ///
+    /// let result: globwalk::GlobWalkerResult = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.rs")
+    ///     .build_parallel()?
+    ///     .collect();
+    /// assert_eq!(result.entries.len(), 3);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`with_thread_count`]: #method.with_thread_count
+    /// [`with_error_limit`]: #method.with_error_limit
+    /// [`max_entries`]: #method.max_entries
+    /// [`contents_first`]: #method.contents_first
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(self) -> Result<ParallelGlobWalker, GlobError> {
+        let shards = self.build_walkers(Some(rayon::current_num_threads()))?;
+        Ok(ParallelGlobWalker { shards })
+    }
+
+    /// Finalize and build a stream of the matches, for use in `tokio` applications.
+    ///
+    /// The walk runs on the blocking thread pool through `tokio::task::spawn_blocking`, so it
+    /// does not block the executor, and its results are sent to the stream through a channel.
+    /// This must be called from within a `tokio` runtime.
+    #[cfg(feature = "tokio")]
+    pub fn build_async(self) -> Result<GlobWalkerStream, GlobError> {
+        Ok(GlobWalkerStream::spawn(self.build()?))
+    }
+
+    /// Build the walkers of a walk.
+    ///
+    /// With `shards`, the walk is split between that many independent walkers. Otherwise, a single
+    /// walker is returned, which splits the walk between threads by itself if requested.
+    fn build_walkers(self, shards: Option<usize>) -> Result<Vec<GlobWalker>, GlobError> {
+        let mut walker = self.walker;
+        if self.min_depth_relative.is_some() || self.max_depth_relative.is_some() {
+            let anchor = anchor_depth(&self.patterns);
+            if let Some(depth) = self.min_depth_relative {
+                walker.min_depth = walker.min_depth.max(anchor.saturating_add(depth));
+            }
+            if let Some(depth) = self.max_depth_relative {
+                walker.max_depth = walker.max_depth.min(anchor.saturating_add(depth));
+            }
+        }
+        let mut predicate = self.predicate;
+        let mut track_symlink_targets = self.track_symlink_targets;
+        let mut metadata_filters = self.metadata_filters;
+        if self.benchmark_mode {
+            walker.sort_by = None;
+            predicate = None;
+            track_symlink_targets = false;
+            metadata_filters = MetadataFilters::default();
+        }
+
+        let min_depth = walker.min_depth;
+        walker.min_depth = 0;
+
+        if self.only.bits().count_ones() > 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "only one of files_only, dirs_only and symlinks_only can be enabled",
+            )
+            .into());
+        }
+        let file_type = match (self.file_type, self.only.is_empty()) {
+            (file_type, true) => file_type,
+            (None, false) => Some(self.only),
+            (Some(file_type), false) => Some(file_type & self.only),
+        };
+
+        metadata_filters.size_of_dirs = file_type == Some(FileType::DIR);
+
+        let deadline = self
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+        if self.require_base_exists && !self.root.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} is not an existing directory", self.root.display()),
+            )
+            .into());
+        }
+        let mut done = self.allow_empty_base && !self.root.exists();
+        #[cfg(unix)]
+        if let Some(device) = self.device {
+            // Errors are left for the walk to report.
+            done |= device_id_of(&self.root).is_ok_and(|root| root != device);
+        }
+        let case_insensitive = if self.auto_case_sensitivity {
+            is_case_insensitive(&self.root).unwrap_or(self.case_insensitive)
+        } else {
+            self.case_insensitive
+        };
+        let mut builder = OverrideBuilder::new(&self.root);
+
+        builder
+            .case_insensitive(case_insensitive)
+            .map_err(GlobError)?;
+
+        let mut patterns = self.patterns;
+        let ignore_file = self.root.join(AUTO_IGNORE_FILE);
+        if self.auto_ignore && ignore_file.is_file() {
+            let reader = std::io::BufReader::new(std::fs::File::open(&ignore_file)?);
+            let ignore_patterns = read_patterns(reader)?;
+            let exclude_file = format!("!/{}", AUTO_IGNORE_FILE);
+            for pattern in ignore_patterns.iter().chain([&exclude_file]) {
+                let mut pattern = Pattern::new(pattern, None);
+                pattern.priority = i32::MAX;
+                patterns.push(pattern);
+            }
+        }
+        for (i, pattern) in patterns.iter_mut().enumerate() {
+            pattern.index = i;
+        }
+        patterns.sort_by_key(|p| p.priority);
+        for pattern in &mut patterns {
+            if let Some(dir) = &self.resolve_dir {
+                pattern.resolve_relative_to(dir, &self.root)?;
+            }
+            #[cfg(feature = "unicode-normalization")]
+            {
+                pattern.pattern = self.unicode_normalization.normalize(&pattern.pattern);
+            }
+            builder
+                .add(&pattern.pattern)
+                .map_err(|e| pattern_error_with_context(&pattern.pattern, e))?;
+        }
+
+        let ignore = builder.build().map_err(GlobError)?;
+
+        let skip_dirs = if self.skip_dirs.is_empty() {
+            None
+        } else {
+            let mut builder = GitignoreBuilder::new(&self.root);
+            builder
+                .case_insensitive(case_insensitive)
+                .map_err(GlobError)?;
+            for pattern in &self.skip_dirs {
+                builder.add_line(None, pattern).map_err(GlobError)?;
+            }
+            Some(builder.build().map_err(GlobError)?)
+        };
+
+        let position = self.resume_from.as_ref().map(|path| self.root.join(path));
+        // Shared by the walkers of a multi-threaded walk, so that each file is yielded once.
+        #[cfg(unix)]
+        let visited_dirs = if self.detect_cycles {
+            Some(Arc::new(std::sync::Mutex::new(HashSet::new())))
+        } else {
+            None
+        };
+        #[cfg(unix)]
+        let yielded_files = if self.deduplicate_inodes {
+            Some(Arc::new(std::sync::Mutex::new(HashSet::new())))
+        } else {
+            None
+        };
+        let contents_first = walker.contents_first;
+        let with_traversal = |traversal: Traversal| GlobWalker {
+            // Breadth-first traversals always visit directories before their contents.
+            contents_first: contents_first && !matches!(traversal, Traversal::BreadthFirst(_)),
+            ignore: ignore.clone(),
+            root: self.root.clone(),
+            patterns: patterns.clone(),
+            case_insensitive,
+            attribution: None,
+            last_match: None,
+            walker: traversal,
+            workers: None,
+            min_depth,
+            logger: None,
+            error_limit: self.error_limit,
+            max_entries: self.max_entries,
+            deadline,
+            held_dirs: if self.matched_contents_first {
+                Some(HeldDirs::default())
+            } else {
+                None
+            },
+            entry_count: 0,
+            error_count: 0,
+            include_root: self.include_root,
+            walk_root_entry: self.walk_root_entry,
+            follow_links_for_dirs_only: self.follow_links_for_dirs_only,
+            #[cfg(unix)]
+            device: self.device,
+            metadata_filters,
+            skip_hidden: self.skip_hidden,
+            gitignores: if self.gitignore {
+                Some(Gitignores::default())
+            } else {
+                None
+            },
+            entry_filter: self.entry_filter.clone(),
+            skip_dirs: skip_dirs.clone(),
+            max_dir_entries: self.max_dir_entries,
+            on_entry: self.on_entry.clone(),
+            on_match: self.on_match.clone(),
+            on_error: self.on_error.clone(),
+            #[cfg(unix)]
+            visited_dirs: visited_dirs.clone(),
+            #[cfg(unix)]
+            yielded_files: yielded_files.clone(),
+            file_type_filter: file_type,
+            filename_encoding: self.filename_encoding,
+            #[cfg(feature = "unicode-normalization")]
+            unicode_normalization: self.unicode_normalization,
+            track_symlink_targets,
+            predicate: predicate.clone(),
+            position: position.clone(),
+            resuming: position.is_some(),
+            max_link_depth: self.max_link_depth,
+            skip_contents: false,
+            deny_symlinks: self.deny_symlinks,
+            done,
+        };
+
+        let threaded = self.thread_count > 1
+            && self.traversal_order == TraversalOrder::DepthFirst
+            && walker.sort_by.is_none()
+            && !walker.contents_first;
+        if position.is_some() && (shards.is_some() || threaded) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "resume_from cannot be combined with multi-threaded walks",
+            )
+            .into());
+        }
+
+        let shard_walkers = |count| {
+            let (root, walker, with_traversal) = (&self.root, &walker, &with_traversal);
+            (0..count).map(move |i| {
+                let shard = Shard::new(root, walker.clone(), i, count);
+                with_traversal(Traversal::Shard(Box::new(shard)))
+            })
+        };
+
+        // A shard skips the directories of the others as they are visited, which happens after
+        // their contents when visiting contents first, so such walks are not split.
+        if let Some(count) = shards.filter(|_| !walker.contents_first) {
+            return Ok(shard_walkers(count.max(1)).collect());
+        }
+
+        if threaded {
+            let mut shards = shard_walkers(self.thread_count);
+            let mut walker = shards.next().unwrap();
+            walker.workers = Some(Workers::Pending(shards.collect()));
+            return Ok(vec![walker]);
+        }
+
+        Ok(vec![with_traversal(match self.traversal_order {
+            TraversalOrder::DepthFirst => {
+                Traversal::DepthFirst(walker.walkdir(&self.root).into_iter())
+            }
+            TraversalOrder::BreadthFirst => {
+                Traversal::BreadthFirst(Box::new(BreadthFirst::new(self.root.clone(), walker)))
+            }
+        })])
+    }
+}
+
+/// An iterator which emits glob-matched patterns.
+///
+/// An instance of this type must be constructed through `GlobWalker`,
+/// which uses a builder-style pattern.
+///
+/// The order of the yielded paths is undefined, unless specified by the user
+/// using `GlobWalker::sort_by`.
+pub struct GlobWalker {
+    ignore: Override,
+    /// The base directory, as given to `walkdir`.
+    root: PathBuf,
+    patterns: Vec<Pattern>,
+    case_insensitive: bool,
+    attribution: Option<Attribution>,
+    /// The index of the pattern that matched the last yielded entry, if attribution is enabled.
+    last_match: Option<usize>,
+    walker: Traversal,
+    /// The walkers of the other shards, in a multi-threaded walk.
+    workers: Option<Workers>,
+    /// Enforced here rather than by `walkdir`, so that shallower entries can be logged.
+    min_depth: usize,
+    logger: Option<MissLogger>,
+    error_limit: Option<usize>,
+    max_entries: Option<usize>,
+    deadline: Option<Instant>,
+    /// The matched directories held back until their contents were yielded, if enabled.
+    held_dirs: Option<HeldDirs>,
+    entry_count: usize,
+    error_count: usize,
+    include_root: bool,
+    walk_root_entry: bool,
+    /// Whether directories are visited after their contents.
+    contents_first: bool,
+    follow_links_for_dirs_only: bool,
+    #[cfg(unix)]
+    device: Option<u64>,
+    metadata_filters: MetadataFilters,
+    skip_hidden: bool,
+    gitignores: Option<Gitignores>,
+    entry_filter: Option<EntryPredicate>,
+    skip_dirs: Option<Gitignore>,
+    max_dir_entries: Option<usize>,
+    on_entry: Option<EntryCallback>,
+    on_match: Option<EntryCallback>,
+    on_error: Option<ErrorHandler>,
+    /// The device and inode numbers of the directories visited so far, if cycles are detected.
+    #[cfg(unix)]
+    visited_dirs: Option<FileIds>,
+    /// The device and inode numbers of the entries yielded so far, if they are deduplicated.
+    #[cfg(unix)]
+    yielded_files: Option<FileIds>,
+    file_type_filter: Option<FileType>,
+    filename_encoding: FilenameEncoding,
+    #[cfg(feature = "unicode-normalization")]
+    unicode_normalization: UnicodeNormalization,
+    track_symlink_targets: bool,
+    predicate: Option<EntryPredicate>,
+    /// The path of the last entry visited, for checkpoints.
+    position: Option<PathBuf>,
+    /// Whether the entries up to `position` are being skipped, after resuming from a checkpoint.
+    resuming: bool,
+    max_link_depth: Option<usize>,
+    /// Whether the contents of the last directory visited are to be skipped, once it was yielded.
+    skip_contents: bool,
+    deny_symlinks: bool,
+    done: bool,
+}
+
+impl std::fmt::Debug for GlobWalker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let patterns: Vec<_> = self.patterns.iter().map(|p| &p.pattern).collect();
+        f.debug_struct("GlobWalker")
+            .field("root", &self.ignore.path())
+            .field("patterns", &patterns)
+            .field("walker", &format_args!("WalkDir {{ .. }}"))
+            .field("entry_count", &self.entry_count)
+            .field("error_count", &self.error_count)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The number of symbolic links in the path of an entry below the base directory, including the
+/// entry itself.
+fn link_depth(entry: &DirEntry) -> usize {
+    let ancestors = entry.path().ancestors().skip(1).take(entry.depth() - 1);
+    let links = ancestors
+        .filter(|path| std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()))
+        .count();
+    links + usize::from(entry.path_is_symlink())
+}
+
+/// The depth of the shallowest directory that the positive patterns are anchored in.
+///
+/// See [`GlobWalkerBuilder::min_depth_relative`].
+fn anchor_depth(patterns: &[Pattern]) -> usize {
+    patterns
+        .iter()
+        .filter(|p| !p.pattern.starts_with('!'))
+        .map(|p| {
+            let pattern = p.pattern.trim_end_matches('/');
+            if !pattern.contains('/') {
+                return 0;
+            }
+            let mut components: Vec<_> = pattern.trim_start_matches('/').split('/').collect();
+            // The last component names the matched entries rather than a directory.
+            components.pop();
+            components
+                .iter()
+                .take_while(|c| !c.contains(['*', '?', '[', '{', '\\']))
+                .count()
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// Strip the base directory from the path of an entry, so that it can be matched against the
+/// patterns.
+///
+/// `walkdir` yields paths starting with `root` as it was given, but `ignore` drops a leading `./`
+/// from it, so both forms are tried. Canonicalizing both sides is the last resort, e.g. for a
+/// base directory that was reached through a symbolic link that has changed since.
+fn relative_path<'a>(root: &Path, ignore_root: &Path, path: &'a Path) -> Option<Cow<'a, Path>> {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return Some(Cow::Borrowed(relative));
+    }
+    if let Ok(relative) = path.strip_prefix(ignore_root) {
+        return Some(Cow::Borrowed(relative));
+    }
+
+    let root = root.canonicalize().ok()?;
+    let path = path.canonicalize().ok()?;
+    path.strip_prefix(root)
+        .ok()
+        .map(|relative| Cow::Owned(relative.to_owned()))
+}
+
+impl GlobWalker {
+    /// Collect all matched entries, failing on the first error encountered.
+    ///
+    /// This is a stable equivalent of the unstable `Iterator::try_collect`, and
+    /// is intended for fallible contexts where any walk error should abort the
+    /// whole operation.
+    pub fn try_collect(self) -> Result<Vec<DirEntry>, WalkError> {
+        self.collect()
+    }
+
+    /// Collect all matched entries and all errors encountered, separately.
+    ///
+    /// This is a shorthand for collecting a [`GlobWalkerResult`], for when the matched entries
+    /// should all be processed while still reporting the errors.
+    ///
+    /// [`GlobWalkerResult`]: struct.GlobWalkerResult.html
+    pub fn collect_results(self) -> (Vec<DirEntry>, Vec<WalkError>) {
+        let GlobWalkerResult { entries, errors } = self.collect();
+        (entries, errors)
+    }
+
+    /// Compute a SHA-256 digest of the matched paths, along with their modification times and sizes.
+    ///
+    /// The entries are sorted by path before hashing, so the digest does not depend on the order
+    /// in which directories are read. Comparing the digests of two walks is a cheap way to tell
+    /// whether anything in the matched tree has changed; the file contents are not read.
+    ///
+    /// Fails on the first walk error, or if the metadata of a matched entry cannot be read.
+    #[cfg(feature = "sha2")]
+    pub fn hash_walk(self) -> Result<[u8; 32], WalkError> {
+        use sha2::{Digest, Sha256};
+        use std::time::UNIX_EPOCH;
+
+        let mut entries = self.try_collect()?;
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+        let mut hasher = Sha256::new();
+        for entry in entries {
+            let metadata = entry.metadata()?;
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+
+            // Fields are NUL-separated, which cannot appear in paths. Paths are hashed as they
+            // are, since distinct invalid names can have the same lossy conversion.
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStrExt;
+                hasher.update(entry.path().as_os_str().as_bytes());
+            }
+            #[cfg(windows)]
+            {
+                use std::os::windows::ffi::OsStrExt;
+                for unit in entry.path().as_os_str().encode_wide() {
+                    hasher.update(unit.to_le_bytes());
+                }
+            }
+            #[cfg(not(any(unix, windows)))]
+            hasher.update(entry.path().to_string_lossy().as_bytes());
+            hasher.update(format!("\0{}\0{}\n", mtime.as_nanos(), metadata.len()));
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    /// Collect the paths of all matched entries into a sorted set.
+    ///
+    /// Errors encountered during the walk are ignored.
+    pub fn into_path_set(self) -> BTreeSet<PathBuf> {
+        self.filter_map(Result::ok)
+            .map(DirEntry::into_path)
+            .collect()
+    }
+
+    /// Collect the matched entries, grouped by the pattern that matched them.
+    ///
+    /// The keys are the patterns as they were given to the builder. Every entry is in the group
+    /// of the pattern that took precedence when matching it, as reported by
+    /// [`with_match_info`], so negated patterns have no group, and neither do patterns that did
+    /// not match anything. Errors encountered during the walk are ignored, as is the base
+    /// directory when it is yielded because of [`GlobWalkerBuilder::include_root`].
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs", "lib.rs", "Cargo.toml"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let groups = globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["*.rs", "*.toml"])
+    ///     .build()?
+    ///     .into_grouped();
+    /// assert_eq!(groups["*.rs"].len(), 2);
+    /// assert_eq!(groups["*.toml"].len(), 1);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`with_match_info`]: #method.with_match_info
+    /// [`GlobWalkerBuilder::include_root`]: struct.GlobWalkerBuilder.html#method.include_root
+    pub fn into_grouped(self) -> HashMap<String, Vec<DirEntry>> {
+        let patterns: Vec<_> = self.patterns().into_iter().map(str::to_owned).collect();
+        let mut groups: HashMap<String, Vec<DirEntry>> = HashMap::new();
+        for matched in self.with_match_info().filter_map(Result::ok) {
+            if let Some(pattern) = matched.pattern_index().and_then(|i| patterns.get(i)) {
+                groups
+                    .entry(pattern.clone())
+                    .or_default()
+                    .push(matched.entry);
+            }
+        }
+        groups
+    }
+
+    /// The compiled patterns this walker matches entries against.
+    ///
+    /// This allows testing other paths against the same patterns, e.g. to check whether a newly
+    /// created file would have been yielded, without building a new walker. Paths are matched
+    /// relative to the base directory; absolute paths under it are made relative automatically.
+    ///
+    /// Note that this only reflects the patterns, and not the other filters of the walker (such as
+    /// the file type or the depth), nor whether a parent directory was excluded.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let walker = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.rs").build()?;
+    /// assert!(walker.pattern_matcher().matched("lib.rs", false).is_whitelist());
+    /// assert!(!walker.pattern_matcher().matched("README.md", false).is_whitelist());
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    pub fn pattern_matcher(&self) -> &Override {
+        &self.ignore
+    }
+
+    /// The patterns of this walker, including negated ones, as they were given to the builder.
+    ///
+    /// The patterns are in the order they were added, even if some were added with a priority,
+    /// and are returned as written, before a lone `*` is anchored as `/*` or relative patterns
+    /// are resolved. This is the order that [`MatchedEntry::pattern_index`] and [`explain`]
+    /// refer to.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let walker = globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["*.rs", "!target"])
+    ///     .build()?;
+    /// assert_eq!(walker.patterns(), ["*.rs", "!target"]);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`MatchedEntry::pattern_index`]: struct.MatchedEntry.html#method.pattern_index
+    /// [`explain`]: #method.explain
+    pub fn patterns(&self) -> Vec<&str> {
+        let mut patterns: Vec<_> = self.patterns.iter().collect();
+        patterns.sort_by_key(|p| p.index);
+        patterns.into_iter().map(|p| p.original.as_str()).collect()
+    }
+
+    /// The number of patterns of this walker, including negated ones.
+    ///
+    /// See [`patterns`](#method.patterns).
+    pub fn pattern_count(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Explain whether the patterns of this walker match `path`, and which pattern decided it.
+    ///
+    /// `path` is either relative to the base directory, or an absolute path under it. Like
+    /// [`pattern_matcher`], this only reflects the patterns, and not the other filters of the
+    /// walker, but it does account for parent directories excluded by a negated pattern.
+    /// Whether `path` is a directory is checked on the file system.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let walker = globwalk::GlobWalkerBuilder::from_patterns(BASE_DIR, &["*.rs", "!target"])
+    ///     .build()?;
+    /// let explanation = walker.explain("target/main.rs".as_ref());
+    /// assert!(!explanation.is_match());
+    /// assert_eq!(explanation.pattern_index(), Some(1));
+    /// println!("{}", explanation);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`pattern_matcher`]: #method.pattern_matcher
+    pub fn explain(&self, path: &Path) -> MatchExplanation {
+        let relative = if path.is_absolute() {
+            relative_path(&self.root, self.ignore.path(), path)
+        } else {
+            Some(Cow::Borrowed(path))
+        };
+        let relative = match relative {
+            Some(relative) => relative.into_owned(),
+            None => {
+                return MatchExplanation {
+                    relative_path: path.to_owned(),
+                    outside: true,
+                    matched: false,
+                    pattern: None,
+                    excluded_ancestor: None,
+                }
+            }
+        };
+
+        let attribution_owned;
+        let attribution = match &self.attribution {
+            Some(attribution) => attribution,
+            None => {
+                attribution_owned =
+                    Attribution::new(self.ignore.path(), &self.patterns, self.case_insensitive);
+                &attribution_owned
+            }
+        };
+        let pattern = |path: &Path, is_dir: bool| {
+            attribution
+                .matched(path, is_dir)
+                .map(|i| (self.patterns[i].index, self.patterns[i].pattern.clone()))
+        };
+
+        // Excluded directories are not descended into, so their contents are never matched.
+        let ancestors: Vec<_> = relative.ancestors().skip(1).collect();
+        for ancestor in ancestors.into_iter().rev() {
+            if !ancestor.as_os_str().is_empty() && self.ignore.matched(ancestor, true).is_ignore() {
+                return MatchExplanation {
+                    pattern: pattern(ancestor, true),
+                    excluded_ancestor: Some(ancestor.to_owned()),
+                    relative_path: relative,
+                    outside: false,
+                    matched: false,
+                };
+            }
+        }
+
+        let is_dir = self.root.join(&relative).is_dir();
+        let (matched, pattern) = match self.ignore.matched(&relative, is_dir) {
+            Match::Whitelist(_) => (true, pattern(&relative, is_dir)),
+            // Files that are not matched by any pattern are also reported as ignored.
+            Match::Ignore(_) => (false, pattern(&relative, is_dir)),
+            Match::None => (false, None),
+        };
+        MatchExplanation {
+            relative_path: relative,
+            outside: false,
+            matched,
+            pattern,
+            excluded_ancestor: None,
+        }
+    }
+
+    /// Take a checkpoint of the position of the walk, from which another walk can be resumed.
+    ///
+    /// The checkpoint records the last entry visited, so a walk resumed from it, with
+    /// [`GlobWalkerBuilder::resume_from`], continues with the entries that this walker has not
+    /// yielded yet. This is `None` before the first entry was visited, for multi-threaded walks,
+    /// and while directories are held back by
+    /// [`GlobWalkerBuilder::matched_contents_first`].
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["a.rs", "b.rs", "c.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let builder = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.rs").sort_by_file_name();
+    /// let mut walker = builder.clone().build()?;
+    /// assert_eq!(walker.next().unwrap()?.file_name(), "a.rs");
+    /// let checkpoint = walker.checkpoint().unwrap();
+    ///
+    /// let mut resumed = builder.resume_from(checkpoint).build()?;
+    /// assert_eq!(resumed.next().unwrap()?.file_name(), "b.rs");
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// [`GlobWalkerBuilder::resume_from`]: struct.GlobWalkerBuilder.html#method.resume_from
+    /// [`GlobWalkerBuilder::matched_contents_first`]: struct.GlobWalkerBuilder.html#method.matched_contents_first
+    pub fn checkpoint(&self) -> Option<WalkCheckpoint> {
+        let holding = self
+            .held_dirs
+            .as_ref()
+            .is_some_and(|held| !held.dirs.is_empty() || !held.ready.is_empty());
+        if self.workers.is_some() || holding {
+            return None;
+        }
+
+        let position = self.position.as_deref()?;
+        Some(WalkCheckpoint {
+            path: position
+                .strip_prefix(&self.root)
+                .unwrap_or(position)
+                .to_owned(),
+        })
+    }
+
+    /// Convert this walker into an iterator over the paths of the matched entries.
+    ///
+    /// Errors are passed through unchanged.
+    pub fn into_path_iter(self) -> impl Iterator<Item = Result<PathBuf, WalkError>> {
+        self.map(|r| r.map(DirEntry::into_path))
+    }
+
+    /// Convert this walker into an iterator over the matched entries along with their depth.
+    ///
+    /// The depth is that of [`DirEntry::depth`]: the base directory has depth `0`, and its
+    /// contents have depth `1`. Errors are passed through unchanged.
+    ///
+    /// [`DirEntry::depth`]: struct.DirEntry.html#method.depth
+    pub fn with_depth(self) -> impl Iterator<Item = Result<(usize, DirEntry), WalkError>> {
+        self.map(|r| r.map(|e| (e.depth(), e)))
+    }
+
+    /// Convert this walker into an iterator over the matched paths, relative to the base directory.
+    ///
+    /// Errors encountered during the walk are ignored.
+    pub fn into_relative_paths(self) -> impl Iterator<Item = PathBuf> {
+        let base = self.root.clone();
+        self.filter_map(Result::ok)
+            .map(move |e| match e.path().strip_prefix(&base) {
+                Ok(relative) => relative.to_owned(),
+                Err(_) => e.into_path(),
+            })
+    }
+
+    /// Collect the canonicalized paths of all matched entries into a sorted set.
+    ///
+    /// Since paths are canonicalized, several entries leading to the same file (e.g. through
+    /// symbolic links) only appear once. Entries that fail to canonicalize, as well as errors
+    /// encountered during the walk, are ignored.
+    pub fn into_abs_path_set(self) -> BTreeSet<PathBuf> {
+        self.filter_map(Result::ok)
+            .filter_map(|e| e.path().canonicalize().ok())
+            .collect()
+    }
+
+    /// Count the matched entries and the errors encountered, without storing either.
+    ///
+    /// Returns `(matched, errors)`.
+    pub fn count_matches(self) -> (usize, usize) {
+        self.fold((0, 0), |(matched, errors), r| match r {
+            Ok(_) => (matched + 1, errors),
+            Err(_) => (matched, errors + 1),
+        })
+    }
+
+    /// Count the matched files by their extension.
+    ///
+    /// Files without an extension are counted under an empty `OsString`.
+    /// Directories, as well as errors encountered during the walk, are not counted.
+    pub fn count_by_extension(self) -> HashMap<OsString, usize> {
+        let mut counts = HashMap::new();
+        for extension in self.matched_extensions() {
+            *counts.entry(extension).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Same as [`count_by_extension`], but the counts are sorted by extension.
+    ///
+    /// [`count_by_extension`]: #method.count_by_extension
+    pub fn count_by_extension_sorted(self) -> BTreeMap<OsString, usize> {
+        let mut counts = BTreeMap::new();
+        for extension in self.matched_extensions() {
+            *counts.entry(extension).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Pair every item yielded by this walker with an item from `other`.
+    ///
+    /// This is the same as `Iterator::zip`; iteration stops as soon as either side is exhausted.
+    pub fn zip_with<I: IntoIterator>(self, other: I) -> std::iter::Zip<Self, I::IntoIter> {
+        self.zip(other)
+    }
+
+    /// Pair every item yielded by this walker with its depth.
+    ///
+    /// Unlike `Iterator::enumerate`, the number is the depth of the entry (or of the error)
+    /// relative to the base directory, as reported by `DirEntry::depth`. This is useful when
+    /// rendering tree views.
+    pub fn enumerate_depth(self) -> impl Iterator<Item = (usize, Result<DirEntry, WalkError>)> {
+        self.map(|item| {
+            let depth = match &item {
+                Ok(e) => e.depth(),
+                Err(e) => e.depth(),
+            };
+            (depth, item)
+        })
+    }
+
+    /// Choose `n` of the matched entries uniformly at random.
+    ///
+    /// The walk is consumed in a single pass using reservoir sampling, so the total number of
+    /// matches does not need to be known in advance. Errors are skipped. If there are fewer than
+    /// `n` matches, all of them are returned.
+    #[cfg(feature = "rand")]
+    pub fn sample<R: rand::Rng + ?Sized>(self, n: usize, rng: &mut R) -> Vec<DirEntry> {
+        let mut reservoir = Vec::with_capacity(n);
+        for (i, entry) in self.filter_map(Result::ok).enumerate() {
+            if i < n {
+                reservoir.push(entry);
+            } else {
+                let j = rng.gen_range(0..=i);
+                if j < n {
+                    reservoir[j] = entry;
+                }
+            }
+        }
+        reservoir
+    }
+
+    /// Sort the matched entries by path and remove duplicates.
+    ///
+    /// This collects the whole walk before yielding anything, and takes O(n log n) time. Errors
+    /// are yielded after all the entries. See also [`dedup_by_path`], which keeps the order of
+    /// the walk.
+    ///
+    /// [`dedup_by_path`]: #method.dedup_by_path
+    pub fn dedup(self) -> impl Iterator<Item = Result<DirEntry, WalkError>> {
+        let (mut entries, errors): (Vec<_>, Vec<_>) = self.partition(Result::is_ok);
+        entries.sort_by(|a, b| match (a, b) {
+            (Ok(a), Ok(b)) => a.path().cmp(b.path()),
+            _ => Ordering::Equal,
+        });
+        entries.dedup_by(|a, b| match (a, b) {
+            (Ok(a), Ok(b)) => a.path() == b.path(),
+            _ => false,
+        });
+        entries.into_iter().chain(errors)
+    }
+
+    /// Remove entries whose path was already yielded.
+    ///
+    /// Unlike [`dedup`], entries are yielded lazily and in the order of the walk, at the cost of
+    /// remembering every yielded path. Errors are passed through.
+    ///
+    /// [`dedup`]: #method.dedup
+    pub fn dedup_by_path(self) -> impl Iterator<Item = Result<DirEntry, WalkError>> {
+        let mut seen = HashSet::new();
+        self.filter(move |item| match item {
+            Ok(e) => seen.insert(e.path().to_owned()),
+            Err(_) => true,
+        })
+    }
+
+    /// Merge several walkers, each already sorted according to `cmp`, into a single sorted walk.
+    ///
+    /// The merge is lazy, and takes O(log k) time per entry for k walkers. It only yields a sorted
+    /// walk if every walker yields its entries in the order defined by `cmp`. For example, a
+    /// depth-first walk whose siblings are sorted by file name yields its entries in the order of
+    /// `Path::cmp`:
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let first = create_files(&["a.rs", "c.rs"])?;
+    /// # let second = create_files(&["b.rs"])?;
+    /// use globwalk::{GlobWalker, GlobWalkerBuilder};
+    ///
+    /// let walkers = [first.path(), second.path()]
+    ///     .iter()
+    ///     .map(|base| {
+    ///         GlobWalkerBuilder::new(base, "*.rs")
+    ///             .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+    ///             .build()
+    ///     })
+    ///     .collect::<Result<_, _>>()?;
+    ///
+    /// let names = GlobWalker::merge_sorted(walkers, |a, b| a.file_name().cmp(b.file_name()))
+    ///     .map(|e| e.map(|e| e.file_name().to_owned()))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert_eq!(names, ["a.rs", "b.rs", "c.rs"]);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    ///
+    /// Entries that compare equal are yielded in the order of `walkers`. Errors are yielded as
+    /// soon as they are encountered.
+    pub fn merge_sorted<F>(walkers: Vec<GlobWalker>, cmp: F) -> MergedGlobWalker<F>
+    where
+        F: Fn(&DirEntry, &DirEntry) -> Ordering,
+    {
+        MergedGlobWalker {
+            refill: (0..walkers.len()).rev().collect(),
+            walkers,
+            heap: BinaryHeap::new(),
+            cmp: Arc::new(cmp),
+        }
+    }
+
+    /// Split the results of this walker into two, according to `predicate`.
+    ///
+    /// The first walker yields the entries for which `predicate` returns `true`, and the second
+    /// yields the rest, along with all the errors encountered during the walk.
+    ///
+    /// Note that this runs the whole walk eagerly and keeps all of its results in memory; for a
+    /// single pass, filtering the walker is cheaper.
+    pub fn into_bipartite<F>(self, predicate: F) -> (CollectedGlobWalker, CollectedGlobWalker)
+    where
+        F: Fn(&DirEntry) -> bool,
+    {
+        let (matching, rest): (Vec<_>, Vec<_>) = self.partition(|item| match item {
+            Ok(e) => predicate(e),
+            Err(_) => false,
+        });
+
+        (
+            CollectedGlobWalker {
+                items: matching.into_iter(),
+            },
+            CollectedGlobWalker {
+                items: rest.into_iter(),
+            },
+        )
+    }
+
+    /// Convert this walker into an iterator that reports which pattern matched each entry.
+    ///
+    /// Determining the responsible pattern requires matching every entry a second time, which is
+    /// why this is not done by the `GlobWalker` iterator itself.
+    pub fn with_match_info(mut self) -> MatchedEntries {
+        self.attribution = Some(Attribution::new(
+            self.ignore.path(),
+            &self.patterns,
+            self.case_insensitive,
+        ));
+        MatchedEntries { walker: self }
+    }
+
+    /// Call `logger` for every entry that was visited but not yielded, along with the reason.
+    ///
+    /// This helps finding out why an expected file is not matched. Entries that are never visited
+    /// are not reported, such as the contents of excluded directories, or entries deeper than the
+    /// maximum depth. Errors are not reported either, since they are yielded by the walker.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs", "README.md"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let walker = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.rs")
+    ///     .build()?
+    ///     .log_misses(|path, reason| eprintln!("skipped {}: {:?}", path.display(), reason));
+    /// assert_eq!(walker.count(), 1);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    pub fn log_misses<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(&Path, SkipReason) + Send + Sync + 'static,
+    {
+        // The negated pattern is found the same way the pattern of a match is.
+        if self.attribution.is_none() {
+            self.attribution = Some(Attribution::new(
+                self.ignore.path(),
+                &self.patterns,
+                self.case_insensitive,
+            ));
+        }
+        self.logger = Some(Arc::new(logger));
+        self
+    }
+
+    /// Convert this walker into an iterator that reports the targets of symbolic links.
+    ///
+    /// Targets are only resolved if the walker was built with
+    /// [`GlobWalkerBuilder::track_symlink_targets`] enabled.
+    ///
+    /// [`GlobWalkerBuilder::track_symlink_targets`]: struct.GlobWalkerBuilder.html#method.track_symlink_targets
+    pub fn symlink_entries(self) -> SymlinkEntries {
+        SymlinkEntries { walker: self }
+    }
+
+    /// Convert this walker into an iterator that can look at the next entry without consuming it.
+    ///
+    /// This is the same as [`Iterator::peekable`], but the returned type can be named without
+    /// spelling out `Peekable<GlobWalker>`.
+    ///
+    /// [`Iterator::peekable`]: https://doc.rust-lang.org/std/iter/trait.Iterator.html#method.peekable
+    pub fn peekable_glob(self) -> PeekableGlobWalker {
+        PeekableGlobWalker {
+            walker: self,
+            peeked: None,
+        }
+    }
+
+    /// Write the path of every matched entry to `writer`, one per line.
+    ///
+    /// Each path is terminated by `\n`; paths that are not valid UTF-8 are converted lossily.
+    /// Returns the first error encountered, either while walking or while writing.
+    pub fn pipe<W: Write>(self, writer: W) -> Result<(), std::io::Error> {
+        self.write_paths(writer, b'\n')
+    }
+
+    /// Same as [`pipe`], but terminates each path with a NUL byte, like `find -print0`.
+    ///
+    /// [`pipe`]: #method.pipe
+    pub fn pipe_nul<W: Write>(self, writer: W) -> Result<(), std::io::Error> {
+        self.write_paths(writer, b'\0')
+    }
+
+    /// Write the path of every matched entry to both `a` and `b`, one per line.
+    ///
+    /// Like [`pipe`], but the walk is only done once. Returns the first error encountered,
+    /// either while walking or while writing to one of the writers.
+    ///
+    /// [`pipe`]: #method.pipe
+    pub fn tee<A: Write, B: Write>(self, mut a: A, mut b: B) -> Result<(), std::io::Error> {
+        for entry in self {
+            let entry = entry?;
+            let line = format!("{}\n", entry.path().to_string_lossy());
+            a.write_all(line.as_bytes())?;
+            b.write_all(line.as_bytes())?;
+        }
+        a.flush()?;
+        b.flush()
+    }
+
+    /// Write the path of every matched entry to `writer` as JSON Lines: one JSON string per line.
+    ///
+    /// Paths that are not valid UTF-8 are converted lossily. Errors encountered while walking
+    /// are written as `null` lines, so that consumers can tell that the output is incomplete,
+    /// and the walk continues. Returns the number of paths written, or the first error
+    /// encountered while writing.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["main.rs"])?;
+    /// # let BASE_DIR = &temp_dir;
+    /// let mut output = Vec::new();
+    /// let count = globwalk::GlobWalkerBuilder::new(BASE_DIR, "*.rs")
+    ///     .build()?
+    ///     .write_json_paths(&mut output)?;
+    /// assert_eq!(count, 1);
+    /// assert!(String::from_utf8(output)?.ends_with("main.rs\"\n"));
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    pub fn write_json_paths<W: Write>(self, mut writer: W) -> Result<usize, std::io::Error> {
+        let mut count = 0;
+        let mut line = String::new();
+        for entry in self {
+            line.clear();
+            match entry {
+                Ok(entry) => {
+                    push_json_string(&mut line, &entry.path().to_string_lossy());
+                    count += 1;
+                }
+                Err(_) => line.push_str("null"),
+            }
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    fn write_paths<W: Write>(self, mut writer: W, terminator: u8) -> Result<(), std::io::Error> {
+        for entry in self {
+            let entry = entry?;
+            writer.write_all(entry.path().to_string_lossy().as_bytes())?;
+            writer.write_all(&[terminator])?;
+        }
+        writer.flush()
+    }
+
+    fn matched_extensions(self) -> impl Iterator<Item = OsString> {
+        self.filter_map(Result::ok)
+            .filter(|e| !e.file_type().is_dir())
+            .map(|e| {
+                e.path()
+                    .extension()
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_default()
+            })
+    }
+}
+
+/// Append `s` to `out` as a JSON string literal.
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The results of a walker, along with the index of the matching pattern, and whether the walker
+/// stopped after it, e.g. because of [`GlobWalkerBuilder::deny_symlinks`].
+///
+/// [`GlobWalkerBuilder::deny_symlinks`]: struct.GlobWalkerBuilder.html#method.deny_symlinks
+type WorkerResult = (Result<DirEntry, WalkError>, Option<usize>, bool);
+
+/// The other threads of a multi-threaded walk.
+///
+/// The threads are only started on the first call to `next`, so that settings applied to the
+/// walker after it was built (such as match attribution) apply to them as well.
+enum Workers {
+    Pending(Vec<GlobWalker>),
+    /// The receiver is only used through `&mut`, but a `Mutex` makes the walker `Sync`.
+    Running(std::sync::Mutex<std::sync::mpsc::Receiver<WorkerResult>>),
+}
+
+impl GlobWalker {
+    fn next_threaded(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        use std::sync::mpsc::{sync_channel, TryRecvError};
+
+        let receiver = match self.workers.take()? {
+            Workers::Pending(walkers) => {
+                let (sender, receiver) = sync_channel(256);
+                for mut walker in walkers {
+                    let sender = sender.clone();
+                    walker.attribution = self.attribution.clone();
+                    walker.logger = self.logger.clone();
+                    // Entries and errors are counted, and matches reported, once they reach this
+                    // walker.
+                    walker.error_limit = None;
+                    walker.max_entries = None;
+                    walker.on_match = None;
+                    walker.on_error = None;
+                    walker.held_dirs = None;
+                    std::thread::spawn(move || {
+                        while let Some(item) = walker.next() {
+                            // The receiving walker was dropped, so nobody is interested anymore.
+                            let stopped = walker.done;
+                            if sender.send((item, walker.last_match, stopped)).is_err() {
+                                break;
+                            }
+                        }
+                    });
+                }
+                receiver
+            }
+            Workers::Running(receiver) => receiver
+                .into_inner()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        };
+
+        // Prefer the results of the other threads, so that they are not blocked on a full channel.
+        let (item, last_match, stopped) = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => {
+                if let Some(item) = self.next_match() {
+                    self.workers = Some(Workers::Running(std::sync::Mutex::new(receiver)));
+                    return Some(item);
+                }
+                receiver.recv().ok()?
+            }
+            Err(TryRecvError::Disconnected) => return self.next_match(),
+        };
+
+        self.last_match = last_match;
+        if stopped {
+            // One thread stopping the walk stops it for the others as well.
+            self.done = true;
+            return Some(item);
+        }
+        self.workers = Some(Workers::Running(std::sync::Mutex::new(receiver)));
+        Some(item)
+    }
+}
+
+impl GlobWalker {
+    /// Get the next item, enforcing the limits of the walk.
+    fn next_limited(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        if self.done {
+            return None;
+        }
+        if self.max_entries == Some(self.entry_count) {
+            // Dropping the workers stops the other threads as well.
+            self.done = true;
+            self.workers = None;
+            return None;
+        }
+
+        let item = if self.held_dirs.is_some() {
+            self.next_contents_first()
+        } else {
+            self.next_unordered()
+        };
+        if self.done {
+            // Dropping the workers stops the other threads as well.
+            self.workers = None;
+        }
+
+        match (item, self.error_limit) {
+            (Some(Err(e)), Some(limit)) => {
+                self.error_count += 1;
+                if self.error_count <= limit {
+                    return Some(Err(e));
+                }
+
+                // Dropping the workers stops the other threads as well.
+                self.done = true;
+                self.workers = None;
+                Some(Err(WalkError(WalkErrorInner::TooManyErrors {
+                    count: self.error_count,
+                    last: Box::new(e),
+                })))
+            }
+            (None, _) => {
+                // The traversals are not all fused.
+                self.done = true;
+                None
+            }
+            (Some(Ok(e)), _) => {
+                self.entry_count += 1;
+                if let Some(on_match) = &self.on_match {
+                    on_match(&e);
+                }
+                Some(Ok(e))
+            }
+            (item, _) => item,
+        }
+    }
+}
+
+impl Iterator for GlobWalker {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match (self.next_limited(), &self.on_error) {
+                (Some(Err(e)), Some(on_error)) => on_error(e),
+                (item, _) => return item,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let entries = self.max_entries.map(|n| n - self.entry_count);
+        // Errors are only bounded by an error limit, which is followed by one final error.
+        let errors = self
+            .error_limit
+            .map(|n| (n + 1).saturating_sub(self.error_count));
+        match (entries, errors) {
+            _ if self.done => (0, Some(0)),
+            (Some(0), _) => (0, Some(0)),
+            (Some(entries), Some(errors)) => (0, entries.checked_add(errors)),
+            _ => (0, None),
+        }
+    }
+}
+
+impl std::iter::FusedIterator for GlobWalker {}
+
+/// The state of [`GlobWalkerBuilder::matched_contents_first`].
+///
+/// [`GlobWalkerBuilder::matched_contents_first`]: struct.GlobWalkerBuilder.html#method.matched_contents_first
+#[derive(Default)]
+struct HeldDirs {
+    /// The matched ancestors of the current entry, from the shallowest to the deepest, along with
+    /// the pattern that matched them.
+    dirs: Vec<(DirEntry, Option<usize>)>,
+    ready: VecDeque<(Result<DirEntry, WalkError>, Option<usize>)>,
+}
+
+impl GlobWalker {
+    fn next_unordered(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        if self.workers.is_some() {
+            self.next_threaded()
+        } else {
+            self.next_match()
+        }
+    }
+
+    fn next_contents_first(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        loop {
+            if let Some((item, last_match)) = self.held_dirs.as_mut()?.ready.pop_front() {
+                self.last_match = last_match;
+                return Some(item);
+            }
+
+            let item = self.next_unordered();
+            let last_match = self.last_match;
+            let held = self.held_dirs.as_mut()?;
+            match item {
+                Some(Ok(e)) => {
+                    // The directories that are not ancestors of this entry have no more contents.
+                    while let Some((dir, _)) = held.dirs.last() {
+                        if dir.depth() < e.depth() && e.path().starts_with(dir.path()) {
+                            break;
+                        }
+                        let (dir, last_match) = held.dirs.pop().unwrap();
+                        held.ready.push_back((Ok(dir), last_match));
+                    }
+                    if e.file_type().is_dir() {
+                        held.dirs.push((e, last_match));
+                    } else {
+                        held.ready.push_back((Ok(e), last_match));
+                    }
+                }
+                Some(Err(e)) => held.ready.push_back((Err(e), last_match)),
+                None => {
+                    let (dir, last_match) = held.dirs.pop()?;
+                    held.ready.push_back((Ok(dir), last_match));
+                }
+            }
+        }
+    }
+}
+
+impl GlobWalker {
+    // Possible optimization - Do not descend into directory that will never be a match
+    fn next_match(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        let mut skip_dir = false;
+        let prune_on_resume =
+            !self.contents_first && matches!(self.walker, Traversal::DepthFirst(_));
+        let log = |path: &Path, reason: SkipReason| {
+            if let Some(logger) = &self.logger {
+                logger(path, reason);
+            }
+        };
+
+        // The outer loop allows us to avoid multiple mutable borrows on `self.walker` when
+        // we want to skip.
+        'skipper: loop {
+            if skip_dir {
+                skip_dir = false;
+                self.skip_contents = false;
+                // The contents of a directory have already been visited when it is yielded last,
+                // and skipping would skip the rest of its parent instead.
+                if !self.contents_first {
+                    self.walker.skip_current_dir();
+                }
+            }
+
+            // The inner loop just advances the iterator until a match is found.
+            loop {
+                if std::mem::take(&mut self.skip_contents) && !self.contents_first {
+                    self.walker.skip_current_dir();
+                }
+                let Some(entry) = self.walker.next() else {
+                    break;
+                };
+                // Checked for errors as well, since a failing file system may only yield errors.
+                if self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    self.done = true;
+                    let (path, depth) = match &entry {
+                        Ok(e) => (e.path().to_owned(), e.depth()),
+                        Err(e) => (e.path().unwrap_or(&self.root).to_owned(), e.depth()),
+                    };
+                    let err =
+                        std::io::Error::new(std::io::ErrorKind::TimedOut, "the walk timed out");
+                    return Some(Err(WalkError(WalkErrorInner::Io { path, depth, err })));
+                }
+                match entry {
+                    Ok(e) => {
+                        if self.resuming {
+                            let target = self.position.as_deref().unwrap_or(Path::new(""));
+                            if e.path() == target {
+                                self.resuming = false;
+                            } else if prune_on_resume
+                                && e.file_type().is_dir()
+                                && !target.starts_with(e.path())
+                            {
+                                // The directories visited before the checkpoint in a depth-first
+                                // walk were finished, unless they contain it.
+                                skip_dir = true;
+                                continue 'skipper;
+                            }
+                            continue;
+                        }
+                        match &mut self.position {
+                            Some(position) => {
+                                let position = position.as_mut_os_string();
+                                position.clear();
+                                position.push(e.path());
+                            }
+                            None => self.position = Some(e.path().to_owned()),
+                        }
+
+                        if let Some(on_entry) = &self.on_entry {
+                            on_entry(&e);
+                        }
+
+                        if self.deny_symlinks && e.depth() > 0 && e.path_is_symlink() {
+                            self.done = true;
+                            let err = std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                format!("{} is a symbolic link", e.path().display()),
+                            );
+                            return Some(Err(WalkError::io(&e, err)));
+                        }
+
+                        // The base directory itself is skipped below, whatever its depth.
+                        if e.depth() > 0 && e.depth() < self.min_depth {
+                            log(e.path(), SkipReason::DepthLimit);
+                            continue;
+                        }
+
+                        let is_dir = e.file_type().is_dir();
+                        if let (Some(max), true) = (self.max_link_depth, is_dir && e.depth() > 0) {
+                            // Linked directories deeper than allowed are visited, but their
+                            // contents are not.
+                            if e.path_is_symlink() && link_depth(&e) > max {
+                                self.skip_contents = true;
+                            }
+                        }
+
+                        if self.skip_hidden
+                            && e.depth() > 0
+                            && e.file_name().to_string_lossy().starts_with('.')
+                        {
+                            log(e.path(), SkipReason::Hidden);
+                            if is_dir {
+                                skip_dir = true;
+                                continue 'skipper;
+                            }
+                            continue;
+                        }
+
+                        if let Some(gitignores) = &mut self.gitignores {
+                            if gitignores.is_ignored(&e) {
+                                log(e.path(), SkipReason::Gitignored);
+                                if is_dir {
+                                    skip_dir = true;
+                                    continue 'skipper;
+                                }
+                                continue;
+                            }
+                        }
+
+                        if let (Some(skip_dirs), true) = (&self.skip_dirs, is_dir && e.depth() > 0)
+                        {
+                            let matched = relative_path(&self.root, self.ignore.path(), e.path())
+                                .map(|path| skip_dirs.matched(path, true));
+                            if let Some(Match::Ignore(glob)) = matched {
+                                log(
+                                    e.path(),
+                                    SkipReason::SkippedDir {
+                                        pattern: glob.original().to_owned(),
+                                    },
+                                );
+                                skip_dir = true;
+                                continue 'skipper;
+                            }
+                        }
+
+                        if let Some(filter) = &self.entry_filter {
+                            if e.depth() > 0 && !filter(&e) {
+                                log(e.path(), SkipReason::EntryFilter);
+                                if is_dir {
+                                    skip_dir = true;
+                                    continue 'skipper;
+                                }
+                                continue;
+                            }
+                        }
+
+                        if self.follow_links_for_dirs_only && e.path_is_symlink() && !is_dir {
+                            log(e.path(), SkipReason::FileTypeFilter);
+                            continue;
+                        }
+
+                        // The base directory was checked when building the walker.
+                        #[cfg(unix)]
+                        if let (Some(device), true) = (self.device, is_dir && e.depth() > 0) {
+                            use std::os::unix::fs::MetadataExt;
+
+                            match e.metadata() {
+                                Ok(metadata) if metadata.dev() == device => {}
+                                Ok(_) => {
+                                    log(
+                                        e.path(),
+                                        SkipReason::MetadataFilter {
+                                            reason: String::from("on another device"),
+                                        },
+                                    );
+                                    skip_dir = true;
+                                    continue 'skipper;
+                                }
+                                Err(err) => return Some(Err(err.into())),
+                            }
+                        }
+
+                        #[cfg(unix)]
+                        if let (Some(visited), true) = (&self.visited_dirs, is_dir) {
+                            use std::os::unix::fs::MetadataExt;
+
+                            match e.metadata() {
+                                Ok(metadata) => {
+                                    let id = (metadata.dev(), metadata.ino());
+                                    if !visited.lock().unwrap().insert(id) {
+                                        log(e.path(), SkipReason::AlreadyVisited);
+                                        skip_dir = true;
+                                        continue 'skipper;
+                                    }
+                                }
+                                Err(err) => return Some(Err(err.into())),
+                            }
+                        }
+
+                        if let (Some(max), true) = (self.max_dir_entries, is_dir && e.depth() > 0) {
+                            // Each directory reaches this point once, so the count needs no cache.
+                            let count = std::fs::read_dir(e.path())
+                                .map(|entries| entries.take(max.saturating_add(1)).count());
+                            if count.is_ok_and(|count| count > max) {
+                                log(e.path(), SkipReason::LargeDir);
+                                skip_dir = true;
+                                continue 'skipper;
+                            }
+                        }
+
+                        let file_type = if e.file_type().is_dir() {
+                            Some(FileType::DIR)
+                        } else if e.file_type().is_file() {
+                            Some(FileType::FILE)
+                        } else if e.file_type().is_symlink() {
+                            Some(FileType::SYMLINK)
+                        } else {
+                            None
+                        };
+                        let file_type_matches = match (self.file_type_filter.as_ref(), file_type) {
+                            (None, _) => true,
+                            (Some(_), None) => false,
+                            (Some(filter), Some(actual)) => filter.contains(actual),
+                        };
+
+                        // Strip the common base directory so that the matcher will be
+                        // able to recognize the file name.
+                        let relative = match relative_path(&self.root, self.ignore.path(), e.path())
+                        {
+                            Some(relative) => relative,
+                            None => {
+                                log(e.path(), SkipReason::NoMatch);
+                                continue;
+                            }
+                        };
+                        let path = relative.as_ref();
+
+                        // The path might be empty after stripping if the current base-directory is matched.
+                        if path.as_os_str().is_empty() {
+                            let root_name = if self.walk_root_entry {
+                                root_name(e.path())
+                            } else {
+                                None
+                            };
+                            let root_matches = root_name
+                                .as_ref()
+                                .is_some_and(|name| self.ignore.matched(name, true).is_whitelist());
+                            if (self.include_root || root_matches)
+                                && self.min_depth == 0
+                                && file_type_matches
+                                && self.predicate.as_ref().map_or(true, |p| p(&e))
+                            {
+                                self.last_match = match (&root_name, &self.attribution) {
+                                    (Some(name), Some(attribution)) if root_matches => {
+                                        attribution.matched(name, true)
+                                    }
+                                    _ => None,
+                                };
+                                return Some(Ok(e));
+                            }
+                            continue 'skipper;
+                        }
+
+                        let lossy;
+                        let path = match self.filename_encoding {
+                            FilenameEncoding::OsString => path,
+                            FilenameEncoding::Utf8 if path.to_str().is_some() => path,
+                            FilenameEncoding::Utf8 => {
+                                log(e.path(), SkipReason::InvalidUtf8);
+                                // Everything below a non-UTF-8 directory is non-UTF-8 as well.
+                                if is_dir {
+                                    skip_dir = true;
+                                    continue 'skipper;
+                                }
+                                continue;
+                            }
+                            FilenameEncoding::Lossy => {
+                                lossy = PathBuf::from(path.to_string_lossy().into_owned());
+                                lossy.as_path()
+                            }
+                        };
+
+                        #[cfg(feature = "unicode-normalization")]
+                        let normalized = self.unicode_normalization.normalize_path(path);
+                        #[cfg(feature = "unicode-normalization")]
+                        let path = normalized.as_deref().unwrap_or(path);
+
+                        match self.ignore.matched(path, is_dir) {
+                            Match::Whitelist(_) => {
+                                if !file_type_matches {
+                                    log(e.path(), SkipReason::FileTypeFilter);
+                                } else if !self.predicate.as_ref().map_or(true, |p| p(&e)) {
+                                    log(
+                                        e.path(),
+                                        SkipReason::MetadataFilter {
+                                            reason: String::from("rejected by the find expression"),
+                                        },
+                                    );
+                                } else {
+                                    match self.metadata_filters.check(&e) {
+                                        Ok(None) => {
+                                            #[cfg(unix)]
+                                            if let Some(yielded) = &self.yielded_files {
+                                                use std::os::unix::fs::MetadataExt;
+
+                                                let metadata = match e.metadata() {
+                                                    Ok(metadata) => metadata,
+                                                    Err(err) => return Some(Err(err.into())),
+                                                };
+                                                let id = (metadata.dev(), metadata.ino());
+                                                if !yielded.lock().unwrap().insert(id) {
+                                                    log(e.path(), SkipReason::DuplicateInode);
+                                                    continue;
+                                                }
+                                            }
+                                            if let Some(attribution) = &self.attribution {
+                                                self.last_match = attribution.matched(path, is_dir);
+                                            }
+                                            return Some(Ok(e));
+                                        }
+                                        Ok(Some(reason)) => log(
+                                            e.path(),
+                                            SkipReason::MetadataFilter {
+                                                reason: String::from(reason),
+                                            },
+                                        ),
+                                        Err(err) => return Some(Err(err)),
+                                    }
+                                }
+                            }
+                            Match::Ignore(_) => {
+                                // When there are whitelist patterns, `Override` also ignores
+                                // the files they do not match.
+                                if self.logger.is_some() {
+                                    let reason = match self
+                                        .attribution
+                                        .as_ref()
+                                        .and_then(|a| a.matched(path, is_dir))
+                                    {
+                                        Some(i) => SkipReason::PatternNegation {
+                                            pattern: self.patterns[i].pattern.clone(),
+                                        },
+                                        None => SkipReason::NoMatch,
+                                    };
+                                    log(e.path(), reason);
+                                }
+
+                                // If the directory is ignored, quit the iterator loop and
+                                // skip-out of this directory.
+                                if is_dir {
+                                    skip_dir = true;
+                                    continue 'skipper;
+                                }
+                            }
+                            Match::None => log(e.path(), SkipReason::NoMatch),
+                        }
+                    }
+                    Err(e) => {
+                        return Some(Err(e.into()));
+                    }
+                }
+            }
+            break;
+        }
+
+        None
+    }
+}
+
+/// The name of the base directory `root`, for [`GlobWalkerBuilder::with_walk_root_entry`].
+///
+/// [`GlobWalkerBuilder::with_walk_root_entry`]: struct.GlobWalkerBuilder.html#method.with_walk_root_entry
+fn root_name(root: &Path) -> Option<PathBuf> {
+    match root.file_name() {
+        Some(name) => Some(PathBuf::from(name)),
+        None => Some(PathBuf::from(root.canonicalize().ok()?.file_name()?)),
+    }
+}
+
+/// The `.gitignore` files of the directories visited by a walk, loaded as they are needed.
+///
+/// Entries are checked against the files of all their ancestors, rather than against a stack of
+/// the files of the current directories, so that this works in any traversal order.
+#[derive(Default)]
+struct Gitignores {
+    /// Directories without a `.gitignore` file are mapped to `None`.
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl Gitignores {
+    fn is_ignored(&mut self, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type().is_dir();
+        // From the parent directory up to the base directory, since deeper files take precedence.
+        for dir in entry.path().ancestors().skip(1).take(entry.depth()) {
+            let matcher = self.matchers.entry(dir.to_owned()).or_insert_with(|| {
+                let path = dir.join(".gitignore");
+                // Like git, invalid lines are ignored rather than failing the walk.
+                path.is_file().then(|| Gitignore::new(path).0)
+            });
+            match matcher
+                .as_ref()
+                .map(|m| m.matched_path_or_any_parents(entry.path(), is_dir))
+            {
+                Some(Match::Ignore(_)) => return true,
+                Some(Match::Whitelist(_)) => return false,
+                Some(Match::None) | None => {}
+            }
+        }
+        false
+    }
+}
+
+/// Finds the pattern responsible for a match.
+///
+/// `Override` does not expose the globs it matched, so the patterns are compiled a second time
+/// into a `Gitignore`, which `Override` is implemented with, and which does expose them.
+#[derive(Clone)]
+struct Attribution {
+    gitignore: Gitignore,
+    /// Maps the patterns, as reported by `gitignore::Glob::original`, to their index.
+    /// Identical patterns map to the last one, which takes precedence.
+    indices: HashMap<String, usize>,
+}
+
+impl Attribution {
+    fn new(root: &Path, patterns: &[Pattern], case_insensitive: bool) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        // These cannot fail, since the same patterns were already compiled into an `Override`.
+        let _ = builder.case_insensitive(case_insensitive);
+        let mut indices = HashMap::new();
+        for (i, pattern) in patterns.iter().enumerate() {
+            let _ = builder.add_line(None, &pattern.pattern);
+            let original = if pattern.pattern.ends_with("\\ ") {
+                pattern.pattern.as_str()
+            } else {
+                pattern.pattern.trim_end()
+            };
+            indices.insert(original.to_owned(), i);
+        }
+
+        Attribution {
+            gitignore: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            indices,
+        }
+    }
+
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<usize> {
+        match self.gitignore.matched(path, is_dir) {
+            Match::Ignore(glob) | Match::Whitelist(glob) => {
+                self.indices.get(glob.original()).copied()
+            }
+            Match::None => None,
+        }
+    }
+}
+
+/// Why a path is matched by the patterns of a walker, or not.
+///
+/// This is returned by [`GlobWalker::explain`], and its `Display` implementation describes the
+/// outcome in a sentence.
+///
+/// [`GlobWalker::explain`]: struct.GlobWalker.html#method.explain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchExplanation {
+    relative_path: PathBuf,
+    outside: bool,
+    matched: bool,
+    pattern: Option<(usize, String)>,
+    excluded_ancestor: Option<PathBuf>,
+}
+
+impl MatchExplanation {
+    /// Whether the path is matched by the patterns.
+    pub fn is_match(&self) -> bool {
+        self.matched
+    }
+
+    /// The path that was tested, relative to the base directory.
+    ///
+    /// For a path outside of the base directory, this is the path as given.
+    pub fn relative_path(&self) -> &Path {
+        &self.relative_path
+    }
+
+    /// The index of the pattern that decided the outcome, among the patterns of the walker.
+    ///
+    /// For a matched path, this is a positive pattern; otherwise, this is the negated pattern that
+    /// excluded the path or one of its parents, or `None` if no pattern matched it.
+    pub fn pattern_index(&self) -> Option<usize> {
+        self.pattern.as_ref().map(|(i, _)| *i)
+    }
+
+    /// The pattern that decided the outcome; see [`pattern_index`].
+    ///
+    /// [`pattern_index`]: #method.pattern_index
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_ref().map(|(_, p)| p.as_str())
+    }
+
+    /// The parent directory that was excluded by a negated pattern, if any.
+    pub fn excluded_ancestor(&self) -> Option<&Path> {
+        self.excluded_ancestor.as_deref()
+    }
+}
+
+impl std::fmt::Display for MatchExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let path = self.relative_path.display();
+        if self.outside {
+            return write!(f, "`{}` is outside of the base directory", path);
+        }
+        match (&self.pattern, &self.excluded_ancestor, self.matched) {
+            (None, _, _) => write!(f, "`{}` is not matched by any pattern", path),
+            (Some((i, pattern)), None, true) => {
+                write!(f, "`{}` is matched by pattern {} (`{}`)", path, i, pattern)
+            }
+            (Some((i, pattern)), None, false) => {
+                write!(f, "`{}` is excluded by pattern {} (`{}`)", path, i, pattern)
+            }
+            (Some((i, pattern)), Some(ancestor), _) => write!(
+                f,
+                "`{}` is excluded because `{}` is excluded by pattern {} (`{}`)",
+                path,
+                ancestor.display(),
+                i,
+                pattern
+            ),
+        }
+    }
+}
+
+/// A matched entry, along with the pattern that matched it.
+#[derive(Debug)]
+pub struct MatchedEntry {
+    /// The matched entry.
+    pub entry: DirEntry,
+    pattern: Option<usize>,
+    group: Option<String>,
+}
+
+impl MatchedEntry {
+    /// The index of the pattern that matched this entry, in [`GlobWalker::patterns`].
+    ///
+    /// Negated patterns count towards the indices too, but never match an entry. This is `None`
+    /// for the base directory, which is not matched by a pattern when it is yielded because of
+    /// [`GlobWalkerBuilder::include_root`].
+    ///
+    /// [`GlobWalker::patterns`]: struct.GlobWalker.html#method.patterns
+    /// [`GlobWalkerBuilder::include_root`]: struct.GlobWalkerBuilder.html#method.include_root
+    pub fn pattern_index(&self) -> Option<usize> {
+        self.pattern
+    }
+
+    /// The name of the pattern group that matched this entry.
+    ///
+    /// This is `None` if the entry was matched by a pattern that was not added through
+    /// [`GlobWalkerBuilder::add_pattern_group`].
+    ///
+    /// [`GlobWalkerBuilder::add_pattern_group`]: struct.GlobWalkerBuilder.html#method.add_pattern_group
+    pub fn matched_group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+}
+
+/// An iterator which emits glob-matched entries along with the pattern that matched them.
+///
+/// This is constructed by [`GlobWalker::with_match_info`].
+///
+/// [`GlobWalker::with_match_info`]: struct.GlobWalker.html#method.with_match_info
+pub struct MatchedEntries {
+    walker: GlobWalker,
+}
+
+impl Iterator for MatchedEntries {
+    type Item = Result<MatchedEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.walker.next()?;
+        let index = self.walker.last_match;
+        let pattern = index.and_then(|i| self.walker.patterns.get(i));
+        Some(entry.map(|entry| MatchedEntry {
+            entry,
+            pattern: pattern.map(|p| p.index),
+            group: pattern.and_then(|p| p.group.clone()),
+        }))
+    }
+}
+
+/// A matched entry, along with the target of the symbolic link it was reached through.
+#[derive(Debug)]
+pub struct SymlinkEntry {
+    /// The matched entry.
+    pub entry: DirEntry,
+    /// The target of the symbolic link, as read by `std::fs::read_link`.
+    ///
+    /// This is `None` if the entry is not a symbolic link, if its target could not be read,
+    /// or if symlink targets are not tracked.
+    pub target: Option<PathBuf>,
+    /// Whether the target of the symbolic link exists.
+    pub target_exists: bool,
+}
+
+/// An iterator which emits glob-matched entries along with the targets of symbolic links.
+///
+/// This is constructed by [`GlobWalker::symlink_entries`].
+///
+/// [`GlobWalker::symlink_entries`]: struct.GlobWalker.html#method.symlink_entries
+pub struct SymlinkEntries {
+    walker: GlobWalker,
+}
+
+impl Iterator for SymlinkEntries {
+    type Item = Result<SymlinkEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let track = self.walker.track_symlink_targets;
+        Some(self.walker.next()?.map(|entry| {
+            let target = if track && entry.path_is_symlink() {
+                std::fs::read_link(entry.path()).ok()
+            } else {
+                None
+            };
+            let target_exists = target.is_some() && entry.path().exists();
+
+            SymlinkEntry {
+                entry,
+                target,
+                target_exists,
+            }
+        }))
+    }
+}
+
+/// A glob walker which can look at the next entry without consuming it.
+///
+/// This is constructed by [`GlobWalker::peekable_glob`].
+///
+/// [`GlobWalker::peekable_glob`]: struct.GlobWalker.html#method.peekable_glob
+#[derive(Debug)]
+pub struct PeekableGlobWalker {
+    walker: GlobWalker,
+    /// The result of the last `peek`, if it wasn't consumed yet; `Some(None)` once exhausted.
+    peeked: Option<Option<Result<DirEntry, WalkError>>>,
+}
+
+impl PeekableGlobWalker {
+    /// Return a reference to the next entry, without advancing the iterator.
+    ///
+    /// Calling this repeatedly returns the same entry, until it is consumed by `next`.
+    pub fn peek(&mut self) -> Option<&Result<DirEntry, WalkError>> {
+        let walker = &mut self.walker;
+        self.peeked.get_or_insert_with(|| walker.next()).as_ref()
+    }
+}
+
+impl Iterator for PeekableGlobWalker {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(item) => item,
+            None => self.walker.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.peeked {
+            Some(None) => (0, Some(0)),
+            Some(Some(_)) => {
+                let (lower, upper) = self.walker.size_hint();
+                (
+                    lower.saturating_add(1),
+                    upper.and_then(|n| n.checked_add(1)),
+                )
+            }
+            None => self.walker.size_hint(),
+        }
+    }
+}
+
+impl std::iter::FusedIterator for PeekableGlobWalker {}
+
+/// An iterator which merges several sorted walkers.
+///
+/// This is constructed by [`GlobWalker::merge_sorted`].
+///
+/// [`GlobWalker::merge_sorted`]: struct.GlobWalker.html#method.merge_sorted
+pub struct MergedGlobWalker<F> {
+    walkers: Vec<GlobWalker>,
+    /// The head entry of every walker that is not exhausted nor waiting to be advanced.
+    heap: BinaryHeap<MergeHead<F>>,
+    /// The walkers whose next entry should be pushed onto the heap.
+    refill: Vec<usize>,
+    cmp: Arc<F>,
+}
+
+struct MergeHead<F> {
+    entry: DirEntry,
+    source: usize,
+    cmp: Arc<F>,
+}
+
+impl<F: Fn(&DirEntry, &DirEntry) -> Ordering> Ord for MergeHead<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap, so the order is reversed to pop the smallest entry first.
+        (self.cmp)(&other.entry, &self.entry).then(other.source.cmp(&self.source))
+    }
+}
+
+impl<F: Fn(&DirEntry, &DirEntry) -> Ordering> PartialOrd for MergeHead<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Fn(&DirEntry, &DirEntry) -> Ordering> PartialEq for MergeHead<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: Fn(&DirEntry, &DirEntry) -> Ordering> Eq for MergeHead<F> {}
+
+impl<F: Fn(&DirEntry, &DirEntry) -> Ordering> Iterator for MergedGlobWalker<F> {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&source) = self.refill.last() {
+            match self.walkers[source].next() {
+                Some(Ok(entry)) => {
+                    self.refill.pop();
+                    self.heap.push(MergeHead {
+                        entry,
+                        source,
+                        cmp: Arc::clone(&self.cmp),
+                    });
+                }
+                // The walker is kept in `refill`, so that it is advanced again on the next call.
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.refill.pop();
+                }
+            }
+        }
+
+        let head = self.heap.pop()?;
+        self.refill.push(head.source);
+        Some(Ok(head.entry))
+    }
+}
+
+/// An iterator over results that were already collected from a walk.
+///
+/// This is constructed by [`GlobWalker::into_bipartite`].
+///
+/// [`GlobWalker::into_bipartite`]: struct.GlobWalker.html#method.into_bipartite
+#[derive(Debug)]
+pub struct CollectedGlobWalker {
+    items: std::vec::IntoIter<Result<DirEntry, WalkError>>,
+}
+
+impl Iterator for CollectedGlobWalker {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl ExactSizeIterator for CollectedGlobWalker {}
+
+/// Get the identifier of the device containing `path`, for [`GlobWalkerBuilder::limit_to_device`].
+///
+/// Symbolic links are followed.
+///
+/// [`GlobWalkerBuilder::limit_to_device`]: struct.GlobWalkerBuilder.html#method.limit_to_device
+#[cfg(unix)]
+pub fn device_id_of<P: AsRef<Path>>(path: P) -> std::io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(std::fs::metadata(path)?.dev())
+}
+
+/// Whether the file system of `dir` is case insensitive, for
+/// [`GlobWalkerBuilder::auto_case_sensitivity`].
+///
+/// Returns `None` if this cannot be told, because `dir` cannot be read or none of its entries has
+/// a name with a case.
+///
+/// [`GlobWalkerBuilder::auto_case_sensitivity`]: struct.GlobWalkerBuilder.html#method.auto_case_sensitivity
+fn is_case_insensitive(dir: &Path) -> Option<bool> {
+    let names: HashSet<OsString> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.file_name()))
+        .collect();
+    for name in names.iter().filter_map(|name| name.to_str()) {
+        let lower = name.to_lowercase();
+        let swapped = if lower != name {
+            lower
+        } else {
+            name.to_uppercase()
+        };
+        if swapped == name {
+            continue;
+        }
+        // Two entries whose names only differ in case can only exist on a case-sensitive volume.
+        if names.contains(OsStr::new(&swapped)) {
+            return Some(false);
+        }
+        return Some(dir.join(swapped).symlink_metadata().is_ok());
+    }
+    None
+}
+
+/// Escape `s` so that it is matched literally when used as a pattern.
+///
+/// The wildcard characters (`*`, `?`, `[`, `]`, `{`, `}` and `\`) are escaped with a backslash,
+/// as are a leading `!` or `#`, which would otherwise negate the pattern or make it a comment.
+/// Trailing whitespace, which would otherwise be trimmed, is wrapped in braces. `/`
+/// keeps separating path components; like any pattern without a slash, an escaped file name
+/// matches at any depth, unless it is prefixed with `/`.
+///
+/// ```rust
+/// assert_eq!(globwalk::glob_escape("notes [draft].txt"), r"notes \[draft\].txt");
+/// assert_eq!(globwalk::glob_escape("!important"), r"\!important");
+/// ```
+pub fn glob_escape(s: &str) -> String {
+    let body = s.trim_end();
+    let mut escaped = String::with_capacity(s.len());
+    if body.starts_with(['!', '#']) {
+        escaped.push('\\');
+    }
+    for c in body.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    for c in s[body.len()..].chars() {
+        escaped.push('{');
+        escaped.push(c);
+        escaped.push('}');
+    }
+    escaped
+}
+
+/// Undo [`glob_escape`], returning the string it was given.
+///
+/// Backslashes are removed from before the characters they escape, and the braces wrapping a
+/// single whitespace character are replaced by that character. Other patterns are
+/// returned mostly unchanged, since their wildcards are not expanded.
+///
+/// [`glob_escape`]: fn.glob_escape.html
+pub fn glob_unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            '{' => {
+                let mut lookahead = chars.clone();
+                match (lookahead.next(), lookahead.next()) {
+                    (Some(ws), Some('}')) if ws.is_whitespace() => {
+                        unescaped.push(ws);
+                        chars = lookahead;
+                    }
+                    _ => unescaped.push(c),
+                }
+            }
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Construct a new `GlobWalkerBuilder` with a glob pattern.
+///
+/// When iterated, the current directory will be recursively searched for paths
+/// matching `pattern`, unless the pattern specifies an absolute path.
+pub fn glob_builder<S: AsRef<str>>(pattern: S) -> GlobWalkerBuilder {
+    // Check to see if the pattern starts with an absolute path
+    let path_pattern: PathBuf = pattern.as_ref().into();
+    if path_pattern.is_absolute() {
+        // If the pattern is an absolute path, split it into the longest base and a pattern.
+        let mut base = PathBuf::new();
+        let mut pattern = PathBuf::new();
+        let mut globbing = false;
+
+        // All `to_str().unwrap()` calls should be valid since the input is a string.
+        for c in path_pattern.components() {
+            let os = c.as_os_str().to_str().unwrap();
+            for c in &["*", "{", "}"][..] {
+                if os.contains(c) {
+                    globbing = true;
+                    break;
+                }
+            }
+
+            if globbing {
+                pattern.push(c);
+            } else {
+                base.push(c);
+            }
+        }
+
+        let pat = pattern.to_str().unwrap();
+        if cfg!(windows) {
+            GlobWalkerBuilder::new(base.to_str().unwrap(), pat.replace('\\', "/"))
+        } else {
+            GlobWalkerBuilder::new(base.to_str().unwrap(), pat)
+        }
+    } else {
+        // If the pattern is relative, start searching from the current directory.
+        GlobWalkerBuilder::new(".", pattern)
+    }
+}
+
+/// Construct a new `GlobWalker` with a glob pattern.
+///
+/// When iterated, the current directory will be recursively searched for paths
+/// matching `pattern`, unless the pattern specifies an absolute path.
+pub fn glob<S: AsRef<str>>(pattern: S) -> Result<GlobWalker, GlobError> {
+    glob_builder(pattern).build()
+}
+
+/// Construct a new `GlobWalker` searching `base` for paths matching `pattern`.
+///
+/// This is a shorthand for `GlobWalkerBuilder::new(base, pattern).build()`.
+///
+/// ```rust
+/// # include!("doctests.rs");
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// # let temp_dir = create_files(&["main.rs", "src/lib.rs"])?;
+/// # let BASE_DIR = &temp_dir;
+/// let walker = globwalk::glob_with_base(BASE_DIR, "**/*.rs")?;
+/// assert_eq!(walker.count(), 2);
+/// # Ok(()) }
+/// # fn main() { run().unwrap() }
+/// ```
+pub fn glob_with_base<P, S>(base: P, pattern: S) -> Result<GlobWalker, GlobError>
+where
+    P: AsRef<Path>,
+    S: AsRef<str>,
+{
+    GlobWalkerBuilder::new(base, pattern).build()
+}
+
+/// Construct a new `GlobWalker` searching `base` for paths matching `patterns`.
+///
+/// This is a shorthand for `GlobWalkerBuilder::from_patterns(base, patterns).build()`; see
+/// [`GlobWalkerBuilder::from_patterns`] for how the patterns are combined.
+///
+/// ```rust
+/// # include!("doctests.rs");
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// # let temp_dir = create_files(&["main.rs", "build.rs", "Cargo.toml"])?;
+/// # let BASE_DIR = &temp_dir;
+/// let walker = globwalk::glob_many(BASE_DIR, &["*.{rs,toml}", "!build.rs"])?;
+/// assert_eq!(walker.count(), 2);
+/// # Ok(()) }
+/// # fn main() { run().unwrap() }
+/// ```
+///
+/// [`GlobWalkerBuilder::from_patterns`]: struct.GlobWalkerBuilder.html#method.from_patterns
+pub fn glob_many<P, S>(base: P, patterns: &[S]) -> Result<GlobWalker, GlobError>
+where
+    P: AsRef<Path>,
+    S: AsRef<str>,
+{
+    GlobWalkerBuilder::from_patterns(base, patterns).build()
+}
+
+/// Construct a new `GlobWalker` from patterns read from `reader`, one per line.
+///
+/// When iterated, the current directory will be recursively searched for paths matching the
+/// patterns. See [`GlobWalkerBuilder::from_reader`] for the format.
+///
+/// [`GlobWalkerBuilder::from_reader`]: struct.GlobWalkerBuilder.html#method.from_reader
+pub fn glob_from_reader<R: BufRead>(reader: R) -> Result<GlobWalker, GlobError> {
+    GlobWalkerBuilder::from_reader(".", reader)?.build()
+}
+
+/// Construct an iterator over the paths matching a glob pattern.
+///
+/// This is the same as [`glob`], but yields paths rather than directory entries; see
+/// [`GlobWalker::into_path_iter`].
+///
+/// [`glob`]: fn.glob.html
+/// [`GlobWalker::into_path_iter`]: struct.GlobWalker.html#method.into_path_iter
+pub fn glob_paths<S: AsRef<str>>(
+    pattern: S,
+) -> Result<impl Iterator<Item = Result<PathBuf, WalkError>>, GlobError> {
+    glob(pattern).map(GlobWalker::into_path_iter)
+}
+
+/// Count the entries matching a glob pattern.
+///
+/// This is the same as [`glob`], but only counts the matches; errors encountered during the walk
+/// are not counted. See [`GlobWalker::count_matches`] for counting them too.
+///
+/// [`glob`]: fn.glob.html
+/// [`GlobWalker::count_matches`]: struct.GlobWalker.html#method.count_matches
+pub fn glob_count<S: AsRef<str>>(pattern: S) -> Result<usize, GlobError> {
+    Ok(glob(pattern)?.count_matches().0)
+}
+
+/// Construct an iterator over the entries matching a glob pattern, along with their depth.
+///
+/// This is the same as [`glob`], but yields the depth of every entry with it; see
+/// [`GlobWalker::with_depth`].
+///
+/// [`glob`]: fn.glob.html
+/// [`GlobWalker::with_depth`]: struct.GlobWalker.html#method.with_depth
+pub fn glob_with_depth<S: AsRef<str>>(
+    pattern: S,
+) -> Result<impl Iterator<Item = Result<(usize, DirEntry), WalkError>>, GlobError> {
+    glob(pattern).map(GlobWalker::with_depth)
+}
+
+/// Construct a stream of the paths matching a glob pattern.
+///
+/// This is the asynchronous analogue of [`glob`]: the current directory is recursively searched
+/// for paths matching `pattern`, unless the pattern specifies an absolute path.
+///
+/// The walk runs on a dedicated thread, so that the file system calls do not block the executor;
+/// the stream works with any executor. It stops as soon as the stream is dropped.
+///
+/// ```rust
+/// use futures::StreamExt;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// futures::executor::block_on(async {
+///     let mut stream = globwalk::glob_stream("src/*.rs").await?;
+///     while let Some(path) = stream.next().await {
+///         println!("{}", path?.display());
+///     }
+///     Ok(())
+/// })
+/// # }
+/// ```
+///
+/// [`glob`]: fn.glob.html
+#[cfg(feature = "async")]
+pub async fn glob_stream(
+    pattern: &str,
+) -> Result<impl futures::Stream<Item = Result<PathBuf, WalkError>>, GlobError> {
+    use futures::SinkExt;
+
+    let walker = glob(pattern)?;
+    let (mut sender, receiver) = futures::channel::mpsc::channel(64);
+    std::thread::spawn(move || {
+        for item in walker.into_path_iter() {
+            // The stream was dropped, so nobody is interested anymore.
+            if futures::executor::block_on(sender.send(item)).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, File};
+    use tempfile::TempDir;
+
+    fn touch(dir: &TempDir, names: &[&str]) {
+        for name in names {
+            let name = normalize_path_sep(name);
+            File::create(dir.path().join(name)).expect("Failed to create a test file");
+        }
+    }
+
+    fn normalize_path_sep<S: AsRef<str>>(s: S) -> String {
+        s.as_ref()
+            .replace("[/]", if cfg!(windows) { "\\" } else { "/" })
+    }
+
+    fn equate_to_expected(g: GlobWalker, mut expected: Vec<String>, dir_path: &Path) {
+        for matched_file in g.into_iter().filter_map(Result::ok) {
+            let path = matched_file
+                .path()
+                .strip_prefix(dir_path)
+                .unwrap()
+                .to_str()
+                .unwrap();
+            let path = normalize_path_sep(path);
+
+            let del_idx = if let Some(idx) = expected.iter().position(|n| &path == n) {
+                idx
+            } else {
+                panic!("Iterated file is unexpected: {}", path);
+            };
+
+            expected.remove(del_idx);
+        }
+
+        // Not equating `.len() == 0` so that the assertion output
+        // will contain the extra files
+        let empty: &[&str] = &[][..];
+        assert_eq!(expected, empty);
+    }
+
+    #[test]
+    fn test_absolute_path() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path().canonicalize().unwrap();
+
+        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+
+        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
+        let mut cwd = dir_path.clone();
+        cwd.push("*.{png,jpg,gif}");
+
+        let glob = glob(cwd.to_str().unwrap()).unwrap();
+        equate_to_expected(glob, expected, &dir_path);
+    }
+
+    #[test]
+    fn test_new() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+
+        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
+
+        let g = GlobWalkerBuilder::new(dir_path, "*.{png,jpg,gif}")
+            .build()
+            .unwrap();
+
+        equate_to_expected(g, expected, dir_path);
+    }
+
+    #[test]
+    fn test_from_patterns() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/some_mod")).expect("");
+        create_dir_all(dir_path.join("tests")).expect("");
+        create_dir_all(dir_path.join("contrib")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.rs",
+                "b.rs",
+                "avocado.rs",
+                "lib.c",
+                "src[/]hello.rs",
+                "src[/]world.rs",
+                "src[/]some_mod[/]unexpected.rs",
+                "src[/]cruel.txt",
+                "contrib[/]README.md",
+                "contrib[/]README.rst",
+                "contrib[/]lib.rs",
+            ][..],
+        );
+
+        let expected: Vec<_> = [
+            "src[/]some_mod[/]unexpected.rs",
+            "src[/]world.rs",
+            "src[/]hello.rs",
+            "lib.c",
+            "contrib[/]lib.rs",
+            "contrib[/]README.md",
+            "contrib[/]README.rst",
+        ]
+        .iter()
+        .map(normalize_path_sep)
+        .collect();
+
+        let patterns = ["src/**/*.rs", "*.c", "**/lib.rs", "**/*.{md,rst}"];
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .build()
+            .unwrap();
+
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_case_insensitive_matching() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/some_mod")).expect("");
+        create_dir_all(dir_path.join("tests")).expect("");
+        create_dir_all(dir_path.join("contrib")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.rs",
+                "b.rs",
+                "avocado.RS",
+                "lib.c",
+                "src[/]hello.RS",
+                "src[/]world.RS",
+                "src[/]some_mod[/]unexpected.rs",
+                "src[/]cruel.txt",
+                "contrib[/]README.md",
+                "contrib[/]README.rst",
+                "contrib[/]lib.rs",
+            ][..],
+        );
+
+        let expected: Vec<_> = [
+            "src[/]some_mod[/]unexpected.rs",
+            "src[/]hello.RS",
+            "src[/]world.RS",
+            "lib.c",
+            "contrib[/]lib.rs",
+            "contrib[/]README.md",
+            "contrib[/]README.rst",
+        ]
+        .iter()
+        .map(normalize_path_sep)
+        .collect();
+
+        let patterns = ["src/**/*.rs", "*.c", "**/lib.rs", "**/*.{md,rst}"];
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_match_dir() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("mod")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.png",
+                "b.png",
+                "c.png",
+                "mod[/]a.png",
+                "mod[/]b.png",
+                "mod[/]c.png",
+            ][..],
+        );
+
+        let expected: Vec<_> = ["mod"].iter().map(normalize_path_sep).collect();
+        let glob = GlobWalkerBuilder::new(dir_path, "mod").build().unwrap();
+
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_blacklist() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/some_mod")).expect("");
+        create_dir_all(dir_path.join("tests")).expect("");
+        create_dir_all(dir_path.join("contrib")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.rs",
+                "b.rs",
+                "avocado.rs",
+                "lib.c",
+                "src[/]hello.rs",
+                "src[/]world.rs",
+                "src[/]some_mod[/]unexpected.rs",
+                "src[/]cruel.txt",
+                "contrib[/]README.md",
+                "contrib[/]README.rst",
+                "contrib[/]lib.rs",
+            ][..],
+        );
+
+        let expected: Vec<_> = [
+            "src[/]some_mod[/]unexpected.rs",
+            "src[/]hello.rs",
+            "lib.c",
+            "contrib[/]lib.rs",
+            "contrib[/]README.md",
+            "contrib[/]README.rst",
+        ]
+        .iter()
+        .map(normalize_path_sep)
+        .collect();
+
+        let patterns = [
+            "src/**/*.rs",
+            "*.c",
+            "**/lib.rs",
+            "**/*.{md,rst}",
+            "!world.rs",
+        ];
+
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .build()
+            .unwrap();
+
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_blacklist_dir() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("Pictures")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.png",
+                "b.png",
+                "c.png",
+                "Pictures[/]a.png",
+                "Pictures[/]b.png",
+                "Pictures[/]c.png",
+            ][..],
+        );
+
+        let expected: Vec<_> = ["a.png", "b.png", "c.png"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+
+        let patterns = ["*.{png,jpg,gif}", "!Pictures"];
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .build()
+            .unwrap();
+
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_glob_with_double_star_pattern() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path().canonicalize().unwrap();
+
+        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+
+        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
+        let mut cwd = dir_path.clone();
+        cwd.push("**");
+        cwd.push("*.{png,jpg,gif}");
+        let glob = glob(cwd.to_str().unwrap()).unwrap();
+        equate_to_expected(glob, expected, &dir_path);
+    }
+
+    #[test]
+    fn test_glob_single_star() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("Pictures")).expect("");
+        create_dir_all(dir_path.join("Pictures").join("b")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.png",
+                "b.png",
+                "c.png",
+                "Pictures[/]a.png",
+                "Pictures[/]b.png",
+                "Pictures[/]c.png",
+                "Pictures[/]b[/]c.png",
+                "Pictures[/]b[/]c.png",
+                "Pictures[/]b[/]c.png",
+            ][..],
+        );
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*")
+            .sort_by(|a, b| a.path().cmp(b.path()))
+            .build()
+            .unwrap();
+        let expected = ["Pictures", "a.png", "b.png", "c.png"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_file_type() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("Pictures")).expect("");
+        create_dir_all(dir_path.join("Pictures").join("b")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.png",
+                "b.png",
+                "c.png",
+                "Pictures[/]a.png",
+                "Pictures[/]b.png",
+                "Pictures[/]c.png",
+                "Pictures[/]b[/]c.png",
+                "Pictures[/]b[/]c.png",
+                "Pictures[/]b[/]c.png",
+            ][..],
+        );
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*")
+            .sort_by(|a, b| a.path().cmp(b.path()))
+            .file_type(FileType::DIR)
+            .build()
+            .unwrap();
+        let expected = ["Pictures"].iter().map(ToString::to_string).collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*")
+            .sort_by(|a, b| a.path().cmp(b.path()))
+            .file_type(FileType::FILE)
+            .build()
+            .unwrap();
+        let expected = ["a.png", "b.png", "c.png"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_try_collect() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+
+        let entries = GlobWalkerBuilder::new(dir_path, "*.{png,jpg,gif}")
+            .build()
+            .unwrap()
+            .try_collect()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("broken.png"))
+                .unwrap();
+            let result = GlobWalkerBuilder::new(dir_path, "*.{png,jpg,gif}")
+                .follow_links(true)
+                .build()
+                .unwrap()
+                .try_collect();
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_filename_encoding() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.txt"][..]);
+        File::create(dir_path.join(OsStr::from_bytes(b"\xff.txt"))).unwrap();
+
+        let count = |enc, pattern| {
+            GlobWalkerBuilder::new(dir_path, pattern)
+                .with_filename_encoding(enc)
+                .build()
+                .unwrap()
+                .filter_map(Result::ok)
+                .count()
+        };
+
+        assert_eq!(count(FilenameEncoding::OsString, "*.txt"), 2);
+        assert_eq!(count(FilenameEncoding::Utf8, "*.txt"), 1);
+        assert_eq!(count(FilenameEncoding::Lossy, "*.txt"), 2);
+        assert_eq!(count(FilenameEncoding::Lossy, "\u{FFFD}.txt"), 1);
+    }
+
+    #[test]
+    fn test_from_env_patterns() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target")).expect("");
+
+        touch(&dir, &["a.rs", "b.toml", "c.md", "target[/]d.rs"][..]);
+
+        std::env::set_var("GLOBWALK_TEST_INCLUDE", "*.rs:*.toml");
+        std::env::set_var("GLOBWALK_TEST_EXCLUDE", "target");
+        let glob = GlobWalkerBuilder::from_env_patterns(
+            dir_path,
+            "GLOBWALK_TEST_INCLUDE",
+            Some("GLOBWALK_TEST_EXCLUDE"),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let expected = ["a.rs", "b.toml"].iter().map(ToString::to_string).collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let glob = GlobWalkerBuilder::from_env_patterns(
+            dir_path,
+            "GLOBWALK_TEST_MISSING",
+            Some("GLOBWALK_TEST_EXCLUDE"),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let expected = ["a.rs", "b.toml", "c.md"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        std::env::set_var("GLOBWALK_TEST_BRACES", "*.{rs,toml}, [,:]*.md");
+        touch(&dir, &[",x.md"][..]);
+        let glob = GlobWalkerBuilder::from_env_patterns(
+            dir_path,
+            "GLOBWALK_TEST_BRACES",
+            Some("GLOBWALK_TEST_EXCLUDE"),
+        )
+        .unwrap();
+        assert_eq!(
+            glob.to_config().patterns,
+            ["*.{rs,toml}", "[,:]*.md", "!target"]
+        );
+        let expected = ["a.rs", "b.toml", ",x.md"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        equate_to_expected(glob.build().unwrap(), expected, dir_path);
+    }
+
+    #[test]
+    fn test_exclude_git_submodules() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("vendor/lib")).expect("");
+        create_dir_all(dir_path.join("src")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "src[/]main.rs",
+                "vendor[/]lib[/]lib.rs",
+                "vendor[/]build.rs",
+            ][..],
+        );
+
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .exclude_git_submodules()
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = [
+            "src[/]main.rs",
+            "vendor[/]lib[/]lib.rs",
+            "vendor[/]build.rs",
+        ]
+        .iter()
+        .map(normalize_path_sep)
+        .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        std::fs::write(
+            dir_path.join(".gitmodules"),
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n\turl = https://example.com/lib.git\n",
+        )
+        .unwrap();
+
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .exclude_git_submodules()
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = ["src[/]main.rs", "vendor[/]build.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_allow_empty_base() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let missing = dir.path().join("missing");
+
+        let mut glob = GlobWalkerBuilder::new(&missing, "*").build().unwrap();
+        assert!(matches!(glob.next(), Some(Err(_))));
+
+        let mut glob = GlobWalkerBuilder::new(&missing, "*")
+            .allow_empty_base(true)
+            .build()
+            .unwrap();
+        assert!(glob.next().is_none());
+    }
+
+    #[test]
+    fn test_into_path_set() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["b.rs", "a.rs", "c.txt"][..]);
+
+        let set = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .build()
+            .unwrap()
+            .into_path_set();
+        let expected: Vec<_> = vec![dir_path.join("a.rs"), dir_path.join("b.rs")];
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), expected);
+
+        let set = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .build()
+            .unwrap()
+            .into_abs_path_set();
+        let canonical = dir_path.canonicalize().unwrap();
+        let expected: Vec<_> = vec![canonical.join("a.rs"), canonical.join("b.rs")];
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_count_by_extension() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src.d")).expect("");
+
+        touch(
+            &dir,
+            &["a.rs", "b.rs", "c.toml", "Makefile", "src.d[/]d.rs"][..],
+        );
+
+        let counts = GlobWalkerBuilder::new(dir_path, "**")
+            .build()
+            .unwrap()
+            .count_by_extension();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&OsString::from("rs")], 3);
+        assert_eq!(counts[&OsString::from("toml")], 1);
+        assert_eq!(counts[&OsString::new()], 1);
+
+        let counts = GlobWalkerBuilder::new(dir_path, "**")
+            .build()
+            .unwrap()
+            .count_by_extension_sorted();
+        let keys: Vec<_> = counts.keys().cloned().collect();
+        assert_eq!(keys, ["", "rs", "toml"].map(OsString::from));
+    }
+
+    #[test]
+    fn test_breadth_first_skips_ignored_dirs() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a/b")).expect("");
+        create_dir_all(dir_path.join("Pictures/b")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.png",
+                "a[/]b.png",
+                "a[/]b[/]c.png",
+                "Pictures[/]a.png",
+                "Pictures[/]b[/]c.png",
+            ][..],
+        );
+
+        let patterns = ["*.png", "!Pictures"];
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .traversal_order(TraversalOrder::BreadthFirst)
+            .build()
+            .unwrap();
+        let expected = ["a.png", "a[/]b.png", "a[/]b[/]c.png"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let depths: Vec<_> = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .traversal_order(TraversalOrder::BreadthFirst)
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|e| e.depth())
+            .collect();
+        assert_eq!(depths, [1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn test_normalize_unicode() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        // "ñ" as `n` followed by a combining tilde (NFD).
+        touch(&dir, &["n\u{303}.txt", "a.txt"][..]);
+
+        // "ñ" as a single code point (NFC).
+        let count = |strategy| {
+            GlobWalkerBuilder::new(dir_path, "\u{f1}.txt")
+                .normalize_unicode(strategy)
+                .build()
+                .unwrap()
+                .filter_map(Result::ok)
+                .count()
+        };
+
+        assert_eq!(count(UnicodeNormalization::None), 0);
+        assert_eq!(count(UnicodeNormalization::Nfc), 1);
+        assert_eq!(count(UnicodeNormalization::Nfd), 1);
+    }
+
+    #[test]
+    fn test_walker_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<GlobWalker>();
+        assert_send_sync::<GlobWalkerBuilder>();
+        assert_send_sync::<GlobWalkerMulti>();
+        assert_send_sync::<CollectedGlobWalker>();
+        assert_send_sync::<MatchedEntries>();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_track_symlink_targets() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.txt"][..]);
+        std::os::unix::fs::symlink(dir_path.join("a.txt"), dir_path.join("b.txt")).unwrap();
+        std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("c.txt")).unwrap();
+
+        let mut entries: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .track_symlink_targets(true)
+            .build()
+            .unwrap()
+            .symlink_entries()
+            .map(Result::unwrap)
+            .map(|e| (e.entry.file_name().to_owned(), e.target, e.target_exists))
+            .collect();
+        entries.sort();
+
+        assert_eq!(
+            entries,
+            [
+                (OsString::from("a.txt"), None, false),
+                (OsString::from("b.txt"), Some(dir_path.join("a.txt")), true),
+                (
+                    OsString::from("c.txt"),
+                    Some(dir_path.join("missing")),
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_walkdir_tweak() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("");
+
+        touch(&dir, &["a.rs", "src[/]b.rs"][..]);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .with_walkdir_tweak(|w| w.max_depth(1))
+            .build()
+            .unwrap();
+        let expected = ["a.rs"].iter().map(ToString::to_string).collect();
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_pipe() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs", "c.txt"][..]);
+
+        let mut out = Vec::new();
+        GlobWalkerBuilder::new(dir_path, "*.rs")
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .build()
+            .unwrap()
+            .pipe(&mut out)
+            .unwrap();
+        let expected = format!(
+            "{}\n{}\n",
+            dir_path.join("a.rs").display(),
+            dir_path.join("b.rs").display()
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut out = Vec::new();
+        GlobWalkerBuilder::new(dir_path, "*.rs")
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .build()
+            .unwrap()
+            .pipe_nul(&mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            expected.replace('\n', "\0")
+        );
+    }
+
+    #[test]
+    fn test_tee() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "c.txt"][..]);
+
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        GlobWalkerBuilder::new(dir_path, "*.rs")
+            .build()
+            .unwrap()
+            .tee(&mut a, &mut b)
+            .unwrap();
+        let expected = format!("{}\n", dir_path.join("a.rs").display());
+        assert_eq!(String::from_utf8(a).unwrap(), expected);
+        assert_eq!(String::from_utf8(b).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs"][..]);
+
+        let pairs: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .build()
+            .unwrap()
+            .zip_with(1..)
+            .map(|(e, i)| (e.unwrap().file_name().to_owned(), i))
+            .collect();
+        assert_eq!(
+            pairs,
+            [(OsString::from("a.rs"), 1), (OsString::from("b.rs"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_add_pattern_group() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.toml", "c.md", "d.txt"][..]);
+
+        let mut groups: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .add_pattern_group("rust", &["*.rs", "*.toml"])
+            .add_pattern_group("docs", &["*.md"])
+            .build()
+            .unwrap()
+            .with_match_info()
+            .map(Result::unwrap)
+            .map(|e| {
+                let name = e.entry.file_name().to_str().unwrap().to_owned();
+                (name, e.matched_group().map(ToOwned::to_owned))
+            })
+            .collect();
+        groups.sort();
+
+        let group = |s: &str| Some(s.to_owned());
+        assert_eq!(
+            groups,
+            [
+                ("a.rs".to_owned(), group("rust")),
+                ("b.toml".to_owned(), group("rust")),
+                ("c.md".to_owned(), group("docs")),
+                ("d.txt".to_owned(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_pairs() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.py", "c.md"][..]);
+
+        let mut tags: Vec<_> =
+            GlobWalkerBuilder::from_pairs(dir_path, &[("*.rs", "rust"), ("*.py", "python")])
+                .build()
+                .unwrap()
+                .with_match_info()
+                .map(Result::unwrap)
+                .map(|e| e.matched_group().unwrap().to_owned())
+                .collect();
+        tags.sort();
+        assert_eq!(tags, ["python", "rust"]);
+    }
+
+    #[test]
+    fn test_enumerate_depth() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/bin")).expect("");
+
+        touch(&dir, &["a.rs", "src[/]b.rs", "src[/]bin[/]c.rs"][..]);
+
+        let mut depths: Vec<_> = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .build()
+            .unwrap()
+            .enumerate_depth()
+            .map(|(depth, e)| (e.unwrap().file_name().to_owned(), depth))
+            .collect();
+        depths.sort();
+        assert_eq!(
+            depths,
+            [
+                (OsString::from("a.rs"), 1),
+                (OsString::from("b.rs"), 2),
+                (OsString::from("c.rs"), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_find_expression() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/bin")).expect("");
+
+        touch(
+            &dir,
+            &["a.rs", "b.toml", "c.md", "src[/]d.rs", "src[/]bin[/]e.RS"][..],
+        );
+        std::fs::write(dir_path.join("big.rs"), vec![0u8; 2048]).unwrap();
+
+        let find = |args| {
+            GlobWalkerBuilder::from_find_expression(dir_path, args)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        let expected = ["a.rs", "big.rs", "src[/]d.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(find("-name '*.rs'"), expected, dir_path);
+
+        let expected = ["a.rs", "big.rs", "src[/]d.rs", "src[/]bin[/]e.RS"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(find("-iname *.rs -type f"), expected, dir_path);
+
+        let expected = ["a.rs", "big.rs", "b.toml"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(
+            find("-maxdepth 1 ( -name *.rs -or -name *.toml )"),
+            expected,
+            dir_path,
+        );
+
+        let expected = ["src", "src[/]bin"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(find("-type d"), expected, dir_path);
+
+        let expected = ["big.rs"].iter().map(normalize_path_sep).collect();
+        equate_to_expected(find("-type f -size +1k"), expected, dir_path);
+
+        let expected = ["b.toml", "c.md", "src"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(
+            find("-mindepth 1 -maxdepth 1 -not -name *.rs"),
+            expected,
+            dir_path,
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_sample() {
+        use rand::SeedableRng;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        let files: Vec<_> = (0..10).map(|i| format!("{}.rs", i)).collect();
+        touch(&dir, &files.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let sample = |n, rng: &mut rand::rngs::StdRng| {
+            GlobWalkerBuilder::new(dir_path, "*.rs")
+                .build()
+                .unwrap()
+                .sample(n, rng)
+        };
+
+        let chosen = sample(3, &mut rng);
+        assert_eq!(chosen.len(), 3);
+        let names: BTreeSet<_> = chosen.iter().map(|e| e.file_name().to_owned()).collect();
+        assert_eq!(names.len(), 3);
+
+        assert_eq!(sample(20, &mut rng).len(), 10);
+        assert!(sample(0, &mut rng).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "sha2")]
+    fn test_hash_walk() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs", "c.md"][..]);
+
+        let hash = || {
+            GlobWalkerBuilder::new(dir_path, "*.rs")
+                .build()
+                .unwrap()
+                .hash_walk()
+                .unwrap()
+        };
+
+        let before = hash();
+        assert_eq!(before, hash());
+
+        std::fs::write(dir_path.join("c.md"), "unmatched").unwrap();
+        assert_eq!(before, hash());
+
+        std::fs::write(dir_path.join("a.rs"), "fn main() {}").unwrap();
+        assert_ne!(before, hash());
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "sha2"))]
+    fn test_hash_walk_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        let first = dir_path.join(OsStr::from_bytes(b"\xff.txt"));
+        let second = dir_path.join(OsStr::from_bytes(b"\xfe.txt"));
+        File::create(&first).unwrap();
+
+        let hash = || {
+            GlobWalkerBuilder::new(dir_path, "*.txt")
+                .with_filename_encoding(FilenameEncoding::Lossy)
+                .build()
+                .unwrap()
+                .hash_walk()
+                .unwrap()
+        };
+
+        let before = hash();
+        // Renaming keeps the modification time, so only the name differs.
+        std::fs::rename(&first, &second).unwrap();
+        assert_ne!(before, hash());
+    }
+
+    #[test]
+    fn test_into_bipartite() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs", "c.md", "d.toml"][..]);
+
+        let (rust, other) = GlobWalkerBuilder::from_patterns(dir_path, &["*.rs", "*.md", "*.toml"])
+            .build()
+            .unwrap()
+            .into_bipartite(|e| e.path().extension() == Some("rs".as_ref()));
+
+        let names = |walker: CollectedGlobWalker| {
+            walker
+                .map(|e| e.unwrap().file_name().to_owned())
+                .collect::<BTreeSet<_>>()
+        };
+
+        assert_eq!(rust.len(), 2);
+        assert_eq!(
+            names(rust),
+            ["a.rs", "b.rs"].iter().map(OsString::from).collect()
+        );
+        assert_eq!(
+            names(other),
+            ["c.md", "d.toml"].iter().map(OsString::from).collect()
+        );
+    }
+
+    #[test]
+    fn test_add_pattern_with_priority() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target")).expect("");
+
+        touch(&dir, &["a.rs", "b.rs", "target[/]c.rs"][..]);
+
+        let g = GlobWalkerBuilder::new(dir_path, "!b.rs")
+            .add_pattern_with_priority("!target", 100)
+            .add_pattern_with_priority("**/*.rs", -1)
+            .build()
+            .unwrap();
+        equate_to_expected(g, vec!["a.rs".into()], dir_path);
+
+        // Without priorities, the last pattern wins.
+        let g = GlobWalkerBuilder::from_patterns(dir_path, &["!b.rs", "**/*.rs", "!target"])
+            .build()
+            .unwrap();
+        equate_to_expected(g, vec!["a.rs".into(), "b.rs".into()], dir_path);
+    }
+
+    #[test]
+    fn test_benchmark_mode() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs"][..]);
+        std::fs::write(dir_path.join("big.rs"), vec![0u8; 2048]).unwrap();
+
+        let g = GlobWalkerBuilder::from_find_expression(dir_path, "-size +1k")
+            .unwrap()
+            .sort_by(|_, _| panic!("sorting should be disabled"))
+            .benchmark_mode(true)
+            .build()
+            .unwrap();
+        equate_to_expected(
+            g,
+            vec!["a.rs".into(), "b.rs".into(), "big.rs".into()],
+            dir_path,
+        );
+    }
+
+    #[test]
+    fn test_resolve_patterns_relative_to() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("project")).expect("");
+        create_dir_all(dir_path.join("shared/nested")).expect("");
+
+        touch(
+            &dir,
+            &[
+                "a.rs",
+                "project[/]b.rs",
+                "shared[/]c.rs",
+                "shared[/]nested[/]d.rs",
+            ][..],
+        );
+
+        let project = dir_path.join("project");
+        let g = GlobWalkerBuilder::from_patterns(dir_path, &["../shared/*.rs", "./*.rs"])
+            .resolve_patterns_relative_to(&project)
+            .build()
+            .unwrap();
+        equate_to_expected(
+            g,
+            vec![
+                normalize_path_sep("shared[/]c.rs"),
+                normalize_path_sep("project[/]b.rs"),
+            ],
+            dir_path,
+        );
+
+        let g = GlobWalkerBuilder::from_patterns(dir_path, &["../**/*.rs", "!./nested"])
+            .resolve_patterns_relative_to(dir_path.join("shared"))
+            .build()
+            .unwrap();
+        equate_to_expected(
+            g,
+            vec![
+                "a.rs".into(),
+                normalize_path_sep("project[/]b.rs"),
+                normalize_path_sep("shared[/]c.rs"),
+            ],
+            dir_path,
+        );
+
+        assert!(GlobWalkerBuilder::new(dir_path, "../../*.rs")
+            .resolve_patterns_relative_to(&project)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("");
+
+        touch(&dir, &["c.rs", "a.rs", "src[/]b.rs"][..]);
+
+        let walker = || GlobWalkerBuilder::new(dir_path, "**/*.rs").build().unwrap();
+
+        let paths: Vec<_> = walker().dedup().map(|e| e.unwrap().into_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                dir_path.join("a.rs"),
+                dir_path.join("c.rs"),
+                dir_path.join("src").join("b.rs"),
+            ]
+        );
+
+        assert_eq!(walker().dedup_by_path().count(), 3);
+    }
+
+    #[test]
+    fn test_merge_sorted() {
+        let first = TempDir::new().expect("Failed to create temporary folder");
+        let second = TempDir::new().expect("Failed to create temporary folder");
+        create_dir_all(first.path().join("d")).expect("");
+
+        touch(&first, &["a.rs", "d[/]e.rs", "f.rs"][..]);
+        touch(&second, &["b.rs", "c.rs", "g.rs"][..]);
+
+        let by_name = |a: &DirEntry, b: &DirEntry| a.file_name().cmp(b.file_name());
+        let walkers = [first.path(), second.path()]
+            .iter()
+            .map(|base| {
+                GlobWalkerBuilder::new(base, "**/*.rs")
+                    .sort_by(by_name)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+
+        let names: Vec<_> = GlobWalker::merge_sorted(walkers, by_name)
+            .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["a.rs", "b.rs", "c.rs", "e.rs", "f.rs", "g.rs"]);
+
+        assert_eq!(GlobWalker::merge_sorted(Vec::new(), by_name).count(), 0);
+    }
+
+    #[test]
+    fn test_with_thread_count() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        let mut files = Vec::new();
+        for d in 0..8 {
+            create_dir_all(dir_path.join(format!("d{}/sub", d))).expect("");
+            files.push(format!("f{}.rs", d));
+            files.push(format!("d{}[/]a.rs", d));
+            files.push(format!("d{}[/]sub[/]b.rs", d));
+            files.push(format!("d{}[/]sub[/]c.txt", d));
+        }
+        touch(&dir, &files.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let walk = |builder: GlobWalkerBuilder| {
+            builder
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().into_path())
+                .collect::<Vec<_>>()
+        };
+        let sorted = |mut paths: Vec<PathBuf>| {
+            paths.sort();
+            paths
+        };
+
+        for patterns in [&["**/*.rs", "!d3"][..], &["*/sub", "*.txt"][..]] {
+            let sequential = walk(GlobWalkerBuilder::from_patterns(dir_path, patterns));
+            let threaded =
+                walk(GlobWalkerBuilder::from_patterns(dir_path, patterns).with_thread_count(4));
+            assert!(!sequential.is_empty());
+            assert_eq!(sorted(sequential), sorted(threaded));
+        }
+
+        let threaded = walk(
+            GlobWalkerBuilder::new(dir_path, "**")
+                .min_depth(2)
+                .max_depth(2)
+                .with_thread_count(3),
+        );
+        assert_eq!(threaded.len(), 16);
+
+        let groups: BTreeSet<_> = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .add_pattern_group("rust", &["*.rs"])
+            .with_thread_count(4)
+            .build()
+            .unwrap()
+            .with_match_info()
+            .map(|e| e.unwrap().matched_group().map(ToOwned::to_owned))
+            .collect();
+        assert_eq!(
+            groups,
+            [None, Some(String::from("rust"))].into_iter().collect()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_with_thread_count_reports_errors_once() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("broken")).unwrap();
+        touch(&dir, &["a.rs", "b.rs"][..]);
+
+        let errors = GlobWalkerBuilder::new(dir_path, "**")
+            .follow_links(true)
+            .with_thread_count(4)
+            .build()
+            .unwrap()
+            .filter(Result::is_err)
+            .count();
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn test_log_misses() {
+        use std::sync::Mutex;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("");
+        create_dir_all(dir_path.join("target")).expect("");
+
+        touch(&dir, &["a.rs", "b.md", "src[/]c.rs", "target[/]d.rs"][..]);
+
+        let misses = |builder: GlobWalkerBuilder| {
+            let misses = Arc::new(Mutex::new(BTreeMap::new()));
+            let logged = Arc::clone(&misses);
+            let base = dir_path.to_owned();
+            let walker = builder.build().unwrap().log_misses(move |path, reason| {
+                let path = path.strip_prefix(&base).unwrap().to_str().unwrap();
+                let path = normalize_path_sep(path);
+                logged.lock().unwrap().insert(path, reason);
+            });
+            walker.for_each(drop);
+
+            let misses = misses.lock().unwrap();
+            misses.clone()
+        };
+        let expect = |misses: &[(&str, SkipReason)]| {
+            misses
+                .iter()
+                .map(|(path, reason)| (normalize_path_sep(path), reason.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        assert_eq!(
+            misses(GlobWalkerBuilder::from_patterns(
+                dir_path,
+                &["**/*.rs", "!target"]
+            )),
+            expect(&[
+                ("b.md", SkipReason::NoMatch),
+                ("src", SkipReason::NoMatch),
+                (
+                    "target",
+                    SkipReason::PatternNegation {
+                        pattern: String::from("!target")
+                    }
+                ),
+            ])
+        );
+
+        assert_eq!(
+            misses(
+                GlobWalkerBuilder::new(dir_path, "**")
+                    .min_depth(2)
+                    .file_type(FileType::DIR)
+            ),
+            expect(&[
+                ("a.rs", SkipReason::DepthLimit),
+                ("b.md", SkipReason::DepthLimit),
+                ("src", SkipReason::DepthLimit),
+                ("target", SkipReason::DepthLimit),
+                ("src[/]c.rs", SkipReason::FileTypeFilter),
+                ("target[/]d.rs", SkipReason::FileTypeFilter),
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_glob_stream() {
+        use futures::StreamExt;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs", "b.rs", "c.md"][..]);
+
+        let pattern = format!("{}/*.rs", dir_path.to_str().unwrap());
+        let paths = futures::executor::block_on(async {
+            let stream = glob_stream(&pattern).await.unwrap();
+            stream.map(Result::unwrap).collect::<BTreeSet<_>>().await
+        });
+        assert_eq!(
+            paths,
+            [dir_path.join("a.rs"), dir_path.join("b.rs")]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_with_error_limit() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        for name in &["x", "y", "z"] {
+            std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join(name)).unwrap();
+        }
+
+        let errors = |limit| {
+            GlobWalkerBuilder::new(dir_path, "*")
+                .follow_links(true)
+                .with_error_limit(limit)
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap_err())
+                .collect::<Vec<_>>()
+        };
+
+        let errors_1 = errors(1);
+        assert_eq!(errors_1.len(), 2);
+        assert_eq!(errors_1[0].error_count(), None);
+        assert!(errors_1[0].io_error().is_some());
+        assert_eq!(errors_1[1].error_count(), Some(2));
+        assert!(std::error::Error::source(&errors_1[1]).is_some());
+
+        let errors_0 = errors(0);
+        assert_eq!(errors_0.len(), 1);
+        assert_eq!(errors_0[0].error_count(), Some(1));
+
+        assert!(errors(3).iter().all(|e| e.error_count().is_none()));
+    }
+
+    #[test]
+    fn test_include_root() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        touch(&dir, &["a.rs"][..]);
+
+        let walk = |builder: GlobWalkerBuilder| {
+            builder
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().depth())
+                .collect::<BTreeSet<_>>()
+        };
+
+        let builder = || GlobWalkerBuilder::new(dir_path, "*.rs");
+        assert_eq!(walk(builder()), [1].into_iter().collect());
+        assert_eq!(
+            walk(builder().include_root(true)),
+            [0, 1].into_iter().collect()
+        );
+        assert_eq!(
+            walk(builder().include_root(true).file_type(FileType::FILE)),
+            [1].into_iter().collect()
+        );
+        assert_eq!(
+            walk(builder().include_root(true).min_depth(1)),
+            [1].into_iter().collect()
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_follow_links_for_dirs_only() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("real")).expect("");
+
+        touch(&dir, &["real[/]a.rs", "b.rs"][..]);
+        std::os::unix::fs::symlink(dir_path.join("real"), dir_path.join("linked")).unwrap();
+        std::os::unix::fs::symlink(dir_path.join("b.rs"), dir_path.join("c.rs")).unwrap();
+
+        let g = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .follow_links_for_dirs_only(true)
+            .build()
+            .unwrap();
+        equate_to_expected(
+            g,
+            vec![
+                "b.rs".into(),
+                normalize_path_sep("real[/]a.rs"),
+                normalize_path_sep("linked[/]a.rs"),
+            ],
+            dir_path,
+        );
+
+        let g = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .follow_links(true)
+            .build()
+            .unwrap();
+        assert_eq!(g.count(), 4);
+    }
+
+    #[test]
+    fn test_into_relative_paths() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("");
+
+        touch(&dir, &["a.rs", "src[/]b.rs"][..]);
+
+        let paths: BTreeSet<_> = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .build()
+            .unwrap()
+            .into_relative_paths()
+            .collect();
+        assert_eq!(
+            paths,
+            [PathBuf::from("a.rs"), Path::new("src").join("b.rs")]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "glob")]
+    fn test_new_with_root_pattern() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        for sub in ["a/src", "b/src/bin", "c"] {
+            create_dir_all(dir_path.join(sub)).expect("Failed to create subfolders");
+        }
+        touch(
+            &dir,
+            &[
+                "a[/]src[/]main.rs",
+                "a[/]src[/]README.md",
+                "b[/]src[/]lib.rs",
+                "b[/]src[/]bin[/]c.rs",
+                "c[/]lib.rs",
+                "c[/]src",
+            ],
+        );
+
+        let root_pattern = dir_path.join("*").join("src");
+        let builders =
+            GlobWalkerBuilder::new_with_root_pattern(root_pattern.to_str().unwrap(), "*.rs")
+                .unwrap();
+        assert_eq!(builders.len(), 2);
+
+        let found: BTreeSet<_> = builders
+            .into_iter()
+            .flat_map(|builder| builder.build().unwrap())
+            .map(|e| e.unwrap().path().strip_prefix(dir_path).unwrap().to_owned())
+            .collect();
+        let expected: BTreeSet<_> = ["a/src/main.rs", "b/src/lib.rs", "b/src/bin/c.rs"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(found, expected);
+
+        assert!(GlobWalkerBuilder::new_with_root_pattern("[", "*.rs").is_err());
+    }
+
+    #[test]
+    fn test_unmatched_brace_error() {
+        let error = |pattern| {
+            GlobWalkerBuilder::new(".", pattern)
+                .build()
+                .expect_err("pattern should be rejected")
+                .to_string()
+        };
+
+        assert_eq!(
+            error("*.{rs"),
+            "in pattern `*.{rs` at position 3: unmatched `{`"
+        );
+        assert_eq!(
+            error("!src/*.rs}"),
+            "in pattern `!src/*.rs}` at position 10: unmatched `}`"
+        );
+        assert_eq!(
+            error("{a,{b}"),
+            "in pattern `{a,{b}` at position 1: unmatched `{`"
+        );
+        assert_eq!(
+            error("[{]{a"),
+            "in pattern `[{]{a` at position 4: unmatched `{`"
+        );
+        assert!(error("a[b").contains("a[b"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_limit_to_device() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "src[/]b.rs"]);
+
+        let device = device_id_of(dir_path).unwrap();
+        let count = |device| {
+            GlobWalkerBuilder::new(dir_path, "*.rs")
+                .limit_to_device(device)
+                .build()
+                .unwrap()
+                .count()
+        };
+        assert_eq!(count(device), 2);
+        assert_eq!(count(device.wrapping_add(1)), 0);
+
+        assert!(device_id_of(dir_path.join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_case_insensitive_per_platform() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["a.RS"]);
+
+        let count = |yes| {
+            GlobWalkerBuilder::new(dir_path, "*.rs")
+                .case_insensitive(true)
+                .case_insensitive_per_platform(yes)
+                .build()
+                .unwrap()
+                .count()
+        };
+        let expected = if cfg!(any(windows, target_os = "macos")) {
+            1
+        } else {
+            0
+        };
+        assert_eq!(count(true), expected);
+        assert_eq!(count(false), 0);
+    }
+
+    #[test]
+    fn test_base_dir_with_dot_prefix() {
+        // `ignore` drops the leading `./` from the base directory, but `walkdir` does not.
+        let walker = GlobWalkerBuilder::new("./src", "lib.rs").build().unwrap();
+        let matches: Vec<_> = walker.map(|e| e.unwrap().into_path()).collect();
+        assert_eq!(matches, [Path::new("./src/lib.rs")]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_base_dir_through_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        create_dir_all(dir_path.join("real/src")).expect("Failed to create subfolders");
+        touch(&dir, &["real[/]a.rs", "real[/]src[/]b.rs", "real[/]c.md"]);
+        symlink(dir_path.join("real"), dir_path.join("link")).unwrap();
+
+        for base in [
+            dir_path.join("link"),
+            dir_path.join("link/../real"),
+            dir_path.join("real/src/.."),
+        ] {
+            let count = GlobWalkerBuilder::new(&base, "*.rs")
+                .build()
+                .unwrap()
+                .map(Result::unwrap)
+                .count();
+            assert_eq!(count, 2, "{}", base.display());
+        }
+    }
+
+    #[test]
+    fn test_time_filters() {
+        use std::time::Duration;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["a.rs", "b.txt"]);
+
+        let metadata = dir_path.join("a.rs").metadata().unwrap();
+        let second = Duration::from_secs(1);
+        let walk = |f: &dyn Fn(GlobWalkerBuilder) -> GlobWalkerBuilder| {
+            f(GlobWalkerBuilder::new(dir_path, "*.rs"))
+                .build()
+                .unwrap()
+                .collect::<Vec<_>>()
+        };
+
+        let mtime = metadata.modified().unwrap();
+        assert!(walk(&|b| b.modified_after(mtime)).is_empty());
+        assert!(walk(&|b| b.modified_before(mtime)).is_empty());
+        assert_eq!(
+            walk(&|b| b
+                .modified_after(mtime - second)
+                .modified_before(mtime + second))
+            .len(),
+            1
+        );
+
+        match metadata.created() {
+            Ok(ctime) => {
+                assert!(walk(&|b| b.created_after(ctime)).is_empty());
+                assert_eq!(walk(&|b| b.created_before(ctime + second)).len(), 1);
+            }
+            Err(_) => {
+                let results = walk(&|b| b.created_after(mtime - second));
+                assert_eq!(results.len(), 1);
+                let err = results[0].as_ref().unwrap_err();
+                assert_eq!(err.path(), Some(dir_path.join("a.rs").as_path()));
+                assert!(err.io_error().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_size_filters() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("logs")).expect("Failed to create subfolders");
+        for (name, len) in [
+            ("empty.log", 0),
+            ("logs/small.log", 10),
+            ("logs/big.log", 2000),
+        ] {
+            File::create(dir_path.join(name))
+                .and_then(|f| f.set_len(len))
+                .expect("Failed to create a test file");
+        }
+
+        let walk = |f: &dyn Fn(GlobWalkerBuilder) -> GlobWalkerBuilder| {
+            let mut names: Vec<_> = f(GlobWalkerBuilder::new(dir_path, "**"))
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(walk(&|b| b.min_size(10)), ["big.log", "logs", "small.log"]);
+        assert_eq!(
+            walk(&|b| b.max_size(10)),
+            ["empty.log", "logs", "small.log"]
+        );
+        assert_eq!(
+            walk(&|b| b.min_size(1).max_size(1000)),
+            ["logs", "small.log"]
+        );
+        assert!(walk(&|b| b.file_type(FileType::DIR).min_size(u64::MAX)).is_empty());
+    }
+
+    #[test]
+    fn test_match_dir_files_only() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("mod")).expect("");
+
+        touch(&dir, &["a.png", "mod[/]a.png", "mod[/]b.png"][..]);
+
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &["mod", "a.png"])
+            .files_only(true)
+            .build()
+            .unwrap();
+        let expected = ["a.png", "mod[/]a.png"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "mod")
+            .dirs_only(true)
+            .build()
+            .unwrap();
+        equate_to_expected(glob, vec![String::from("mod")], dir_path);
+
+        assert!(GlobWalkerBuilder::new(dir_path, "mod")
+            .files_only(true)
+            .dirs_only(true)
+            .build()
+            .is_err());
+        assert!(GlobWalkerBuilder::new(dir_path, "mod")
+            .files_only(true)
+            .dirs_only(true)
+            .dirs_only(false)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_skip_hidden() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join(".git/objects")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                ".hidden_file.txt",
+                "visible.txt",
+                "src[/].env",
+                "src[/]main.txt",
+                ".git[/]objects[/]ab.txt",
+            ],
+        );
+
+        let visited = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&visited);
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .skip_hidden(true)
+            .build()
+            .unwrap()
+            .log_misses(move |path, _| log.lock().unwrap().push(path.to_owned()));
+        let expected = ["visible.txt", "src[/]main.txt"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let visited = visited.lock().unwrap();
+        assert!(visited.contains(&dir_path.join(".git")));
+        assert!(!visited
+            .iter()
+            .any(|p| p.starts_with(dir_path.join(".git/objects"))));
+    }
+
+    #[test]
+    fn test_with_gitignore() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target/debug")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("src/generated")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "main.rs",
+                "target[/]debug[/]build.rs",
+                "src[/]lib.rs",
+                "src[/]scratch.rs",
+                "src[/]generated[/]a.rs",
+                "src[/]generated[/]keep.rs",
+            ],
+        );
+        std::fs::write(dir_path.join(".gitignore"), "target/\n*scratch*\n").unwrap();
+        std::fs::write(dir_path.join("src/.gitignore"), "generated/*\n!keep.rs\n").unwrap();
+
+        let expected = ["main.rs", "src[/]lib.rs", "src[/]generated[/]keep.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        let glob = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .with_gitignore(true)
+            .build()
+            .unwrap();
+        equate_to_expected(glob, expected, dir_path);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .with_gitignore(true)
+            .contents_first(true)
+            .build()
+            .unwrap();
+        assert_eq!(glob.count(), 3);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*.rs").build().unwrap();
+        assert_eq!(glob.count(), 6);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_collect_results() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("denied")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "b.rs", "denied[/]c.rs"]);
+        symlink(dir_path.join("missing"), dir_path.join("broken.rs")).unwrap();
+
+        let denied = dir_path.join("denied");
+        std::fs::set_permissions(&denied, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Privileged users can read the directory anyway.
+        let readable = std::fs::read_dir(&denied).is_ok();
+
+        let (entries, errors) = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .follow_links(true)
+            .build()
+            .unwrap()
+            .collect_results();
+        std::fs::set_permissions(&denied, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(entries.len(), if readable { 3 } else { 2 });
+        assert_eq!(errors.len(), if readable { 1 } else { 2 });
+        assert!(errors
+            .iter()
+            .any(|e| e.path() == Some(dir_path.join("broken.rs").as_path())));
+        if !readable {
+            assert!(errors.iter().any(|e| e.path() == Some(denied.as_path())));
+        }
+    }
+
+    #[test]
+    fn test_glob_paths() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path().canonicalize().unwrap();
+
+        touch(&dir, &["a.rs", "b.rs", "c.md"][..]);
+
+        let pattern = dir_path.join("*.rs");
+        let paths: BTreeSet<_> = glob_paths(pattern.to_str().unwrap())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            paths,
+            [dir_path.join("a.rs"), dir_path.join("b.rs")]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_fused() {
+        fn assert_fused<I: std::iter::FusedIterator>(iter: I) -> I {
+            iter
+        }
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        touch(&dir, &["a.rs"]);
+
+        let mut glob = assert_fused(GlobWalkerBuilder::new(dir.path(), "*.rs").build().unwrap());
+        assert!(glob.next().is_some());
+        assert!(glob.next().is_none());
+        assert!(glob.next().is_none());
+    }
+
+    #[test]
+    fn test_add_pattern() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["main.rs", "Cargo.toml", "README.md", "build.rs"]);
+
+        let glob = GlobWalkerBuilder::from_patterns::<_, &str>(dir_path, &[])
+            .add_pattern("*.rs")
+            .add_pattern("*.toml")
+            .build()
+            .unwrap();
+        let expected = ["main.rs", "Cargo.toml", "build.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let glob = GlobWalkerBuilder::new(dir_path, "*.md")
+            .add_patterns(&["*.rs", "!build.rs"])
+            .build()
+            .unwrap();
+        let expected = ["main.rs", "README.md"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        use std::io::Cursor;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["main.rs", "build.rs", "Cargo.toml", "README.md"]);
+
+        let patterns = "# Sources\n*.rs\n\n  !build.rs  \n#*.md\n*.toml\n";
+        let glob = GlobWalkerBuilder::from_reader(dir_path, Cursor::new(patterns.as_bytes()))
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = ["main.rs", "Cargo.toml"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let invalid: &[u8] = b"*.rs\n\xff\n";
+        assert!(GlobWalkerBuilder::from_reader(dir_path, Cursor::new(invalid)).is_err());
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        let files: Vec<_> = (0..10).map(|i| format!("{}.rs", i)).collect();
+        touch(&dir, &files.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let walker = |n| {
+            GlobWalkerBuilder::new(dir_path, "*.rs")
+                .max_entries(n)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(walker(3).count(), 3);
+        assert_eq!(walker(20).count(), 10);
+
+        let mut empty = walker(0);
+        assert_eq!(empty.size_hint(), (0, Some(0)));
+        assert!(empty.next().is_none());
+
+        let mut threaded = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .max_entries(4)
+            .with_thread_count(3)
+            .build()
+            .unwrap();
+        assert_eq!(threaded.size_hint(), (0, None));
+        assert_eq!(threaded.by_ref().count(), 4);
+        assert_eq!(threaded.size_hint(), (0, Some(0)));
+
+        let bounded = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .max_entries(5)
+            .with_error_limit(2)
+            .build()
+            .unwrap();
+        // The final error of the error limit is included.
+        assert_eq!(bounded.size_hint(), (0, Some(8)));
+    }
+
+    #[test]
+    fn test_clone_builder() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "b.md", "src[/]c.rs"]);
+
+        let template = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        let shallow = template.clone().max_depth(1).build().unwrap();
+        let deep = template.add_pattern("*.md").build().unwrap();
+
+        let names = |walker: GlobWalker| {
+            walker
+                .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(shallow), ["a.rs"]);
+        assert_eq!(names(deep), ["a.rs", "b.md", "c.rs"]);
+    }
+
+    #[test]
+    fn test_filter_entry() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("skip_me/nested")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("src/skip_me")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "a.txt",
+                "skip_me[/]b.txt",
+                "skip_me[/]nested[/]c.txt",
+                "src[/]d.txt",
+                "src[/]skip_me[/]e.txt",
+            ],
+        );
+
+        let visited = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&visited);
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .filter_entry(move |e| {
+                log.lock().unwrap().push(e.path().to_owned());
+                e.file_name() != "skip_me"
+            })
+            .build()
+            .unwrap();
+        let expected = ["a.txt", "src[/]d.txt"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let visited = visited.lock().unwrap();
+        assert!(visited.contains(&dir_path.join("src/skip_me")));
+        assert!(!visited
+            .iter()
+            .any(|p| p.parent().is_some_and(|p| p.ends_with("skip_me"))));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_detect_cycles() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("b")).expect("Failed to create subfolders");
+        touch(&dir, &["a[/]x.txt"][..]);
+        std::os::unix::fs::symlink(dir_path, dir_path.join("a/root")).unwrap();
+        std::os::unix::fs::symlink(dir_path.join("a"), dir_path.join("b/a")).unwrap();
+
+        let g = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .follow_links(true)
+            .detect_cycles(true)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .build()
+            .unwrap();
+        equate_to_expected(g, vec![normalize_path_sep("a[/]x.txt")], dir_path);
+
+        let g = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .follow_links(true)
+            .build()
+            .unwrap();
+        assert_eq!(g.filter_map(Result::ok).count(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_detect_cycles_threaded() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target")).expect("Failed to create subfolders");
+        touch(&dir, &["target[/]f.rs"][..]);
+        for i in 0..8 {
+            std::os::unix::fs::symlink(
+                dir_path.join("target"),
+                dir_path.join(format!("link{}", i)),
+            )
+            .unwrap();
+        }
+
+        let count = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .follow_links(true)
+            .detect_cycles(true)
+            .with_thread_count(4)
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .count();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_sort_presets() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        for (name, size) in [("c.txt", 1), ("a.txt", 3), ("b.txt", 2)] {
+            std::fs::write(dir_path.join(name), vec![0; size]).unwrap();
+        }
+
+        let names = |builder: GlobWalkerBuilder| -> Vec<String> {
+            builder
+                .build()
+                .unwrap()
+                .filter_map(Result::ok)
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        };
+        let builder = GlobWalkerBuilder::new(dir_path, "*.txt");
+        assert_eq!(
+            names(builder.clone().sort_by_file_name()),
+            ["a.txt", "b.txt", "c.txt"]
+        );
+        assert_eq!(
+            names(builder.clone().sort_by_file_name_reversed()),
+            ["c.txt", "b.txt", "a.txt"]
+        );
+        assert_eq!(
+            names(builder.clone().sort_by_file_size()),
+            ["c.txt", "b.txt", "a.txt"]
+        );
+        assert_eq!(
+            names(builder.sort_by_file_size_reversed()),
+            ["a.txt", "b.txt", "c.txt"]
+        );
+    }
+
+    #[test]
+    fn test_with_depth() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a/b")).expect("Failed to create subfolders");
+        touch(&dir, &["x.txt", "a[/]y.txt", "a[/]b[/]z.txt"][..]);
+
+        let mut depths: Vec<_> = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .build()
+            .unwrap()
+            .with_depth()
+            .map(|r| {
+                let (depth, e) = r.unwrap();
+                (depth, e.file_name().to_string_lossy().into_owned())
+            })
+            .collect();
+        depths.sort();
+        assert_eq!(
+            depths,
+            [
+                (1, "x.txt".to_owned()),
+                (2, "y.txt".to_owned()),
+                (3, "z.txt".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_build_and_collect() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["a.txt", "b.txt", "c.rs"][..]);
+
+        let entries = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .try_build_and_collect()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let err = GlobWalkerBuilder::new(dir_path, "{a,b")
+            .try_build_and_collect()
+            .unwrap_err();
+        assert!(matches!(err, GlobWalkError::Build(_)));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("d.txt")).unwrap();
+            let err = GlobWalkerBuilder::new(dir_path, "*.txt")
+                .follow_links(true)
+                .try_build_and_collect()
+                .unwrap_err();
+            assert!(matches!(err, GlobWalkError::Walk(_)));
+        }
+    }
+
+    #[test]
+    fn test_skip_dirs_matching() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target/debug")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("src/target")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("docs")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "lib.rs",
+                "target[/]a.rs",
+                "target[/]debug[/]b.rs",
+                "src[/]main.rs",
+                "src[/]target[/]c.rs",
+                "docs[/]d.rs",
+            ],
+        );
+
+        let visited = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&visited);
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .skip_dirs_matching("target")
+            .unwrap()
+            .skip_dirs_matching("/docs")
+            .unwrap()
+            .filter_entry(move |e| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                !e.path().ancestors().any(|p| p.ends_with("target"))
+            })
+            .build()
+            .unwrap();
+        let expected = ["lib.rs", "src[/]main.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+        // `lib.rs`, `src` and `src/main.rs`; the skipped directories are pruned before the filter.
+        assert_eq!(visited.load(std::sync::atomic::Ordering::Relaxed), 3);
+
+        assert!(GlobWalkerBuilder::new(dir_path, "*")
+            .skip_dirs_matching("{a,b")
+            .is_err());
+    }
+
+    #[test]
+    fn test_sort_by_name_case_insensitive() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["c.txt", "B.txt", "a.txt"][..]);
+
+        let names: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .sort_by_name_case_insensitive()
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a.txt", "B.txt", "c.txt"]);
+    }
+
+    #[test]
+    #[cfg(feature = "icu_collator")]
+    fn test_sort_by_locale_name() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["z.txt", "\u{e4}.txt", "b.txt", "a.txt"][..]);
+
+        let names: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .sort_by_locale_name()
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a.txt", "\u{e4}.txt", "b.txt", "z.txt"]);
+    }
+
+    #[test]
+    fn test_on_entry_and_on_match() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "b.txt", "src[/]c.rs", "src[/]d.txt"][..]);
+
+        for threads in [1, 2] {
+            let entries = Arc::new(AtomicUsize::new(0));
+            let matches = Arc::new(AtomicUsize::new(0));
+            let (entry_counter, match_counter) = (Arc::clone(&entries), Arc::clone(&matches));
+            let count = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+                .with_thread_count(threads)
+                .on_entry(move |_| {
+                    entry_counter.fetch_add(1, Ordering::Relaxed);
+                })
+                .on_match(move |_| {
+                    match_counter.fetch_add(1, Ordering::Relaxed);
+                })
+                .build()
+                .unwrap()
+                .count();
+            assert_eq!(count, 2);
+            // The base directory, `src` and the 4 files.
+            assert_eq!(entries.load(Ordering::Relaxed), 6);
+            assert_eq!(matches.load(Ordering::Relaxed), 2);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("target")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &["main.rs", "skip.rs", "README.md", "target[/]a.rs"][..],
+        );
+
+        let walker =
+            GlobWalkerBuilder::from_patterns(dir_path, &["*.md", "*.rs", "!skip.rs", "!target"])
+                .build()
+                .unwrap();
 
-            let del_idx = if let Some(idx) = expected.iter().position(|n| &path == n) {
-                idx
-            } else {
-                panic!("Iterated file is unexpected: {}", path);
-            };
+        let explanation = walker.explain(&dir_path.join("main.rs"));
+        assert!(explanation.is_match());
+        assert_eq!(explanation.relative_path(), Path::new("main.rs"));
+        assert_eq!(explanation.pattern_index(), Some(1));
+        assert_eq!(
+            explanation.to_string(),
+            "`main.rs` is matched by pattern 1 (`*.rs`)"
+        );
 
-            expected.remove(del_idx);
+        let explanation = walker.explain(Path::new("skip.rs"));
+        assert!(!explanation.is_match());
+        assert_eq!(explanation.pattern(), Some("!skip.rs"));
+
+        let explanation = walker.explain(&Path::new("target").join("a.rs"));
+        assert!(!explanation.is_match());
+        assert_eq!(explanation.pattern_index(), Some(3));
+        assert_eq!(explanation.excluded_ancestor(), Some(Path::new("target")));
+
+        let explanation = walker.explain(Path::new("lib.txt"));
+        assert!(!explanation.is_match());
+        assert_eq!(explanation.pattern_index(), None);
+        assert_eq!(
+            explanation.to_string(),
+            "`lib.txt` is not matched by any pattern"
+        );
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let builder = GlobWalkerBuilder::from_patterns("src", &["**/*.rs", "!target"])
+            .min_depth(1)
+            .max_depth(3)
+            .follow_links(true)
+            .max_open(4)
+            .case_insensitive(true);
+        let config = builder.to_config();
+        assert_eq!(config.patterns, ["**/*.rs", "!target"]);
+        assert_eq!(
+            GlobWalkerBuilder::from_config(config.clone()).to_config(),
+            config
+        );
+        assert_eq!(
+            GlobWalkerBuilder::new(".", "*.rs").to_config(),
+            GlobWalkerConfig {
+                patterns: vec!["*.rs".to_owned()],
+                ..GlobWalkerConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_config_serde() {
+        let config = GlobWalkerBuilder::from_patterns("src", &["**/*.rs", "!target"])
+            .max_depth(3)
+            .to_config();
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: GlobWalkerConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, config);
+
+        let parsed: GlobWalkerConfig =
+            serde_json::from_str(r#"{"root": "docs", "patterns": ["*.md"]}"#).unwrap();
+        assert_eq!(parsed.root, Path::new("docs"));
+        assert_eq!(parsed.max_depth, usize::MAX);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        for i in 0..20 {
+            let sub = dir_path.join(format!("dir{}", i));
+            create_dir_all(&sub).expect("Failed to create subfolders");
+            for j in 0..50 {
+                File::create(sub.join(format!("{}.txt", j))).expect("Failed to create a test file");
+            }
         }
 
-        // Not equating `.len() == 0` so that the assertion output
-        // will contain the extra files
-        let empty: &[&str] = &[][..];
-        assert_eq!(expected, empty);
+        let results: Vec<_> = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .timeout(Duration::from_nanos(1))
+            .build()
+            .unwrap()
+            .collect();
+        assert!(results.len() < 1000);
+        let err = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(
+            err.io_error().map(std::io::Error::kind),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+
+        let count = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .timeout(Duration::from_secs(3600))
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .count();
+        assert_eq!(count, 1000);
     }
 
     #[test]
-    fn test_absolute_path() {
+    fn test_count_matches() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
-        let dir_path = dir.path().canonicalize().unwrap();
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "b.txt", "src[/]c.rs", "src[/]d.rs"][..]);
 
-        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+        let walker = GlobWalkerBuilder::new(dir_path, "**/*.rs").build().unwrap();
+        assert_eq!(walker.count_matches(), (3, 0));
 
-        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
-        let mut cwd = dir_path.clone();
-        cwd.push("*.{png,jpg,gif}");
+        let pattern = format!("{}/**/*.rs", dir_path.to_str().unwrap());
+        assert_eq!(glob_count(pattern).unwrap(), 3);
 
-        let glob = glob(cwd.to_str().unwrap().to_owned()).unwrap();
-        equate_to_expected(glob, expected, &dir_path);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("e.rs")).unwrap();
+            let walker = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+                .follow_links(true)
+                .build()
+                .unwrap();
+            assert_eq!(walker.count_matches(), (3, 1));
+        }
     }
 
     #[test]
-    fn test_new() {
+    fn test_validate() {
+        let builder = GlobWalkerBuilder::from_patterns(".", &["*.rs", "!target", "src/**"]);
+        assert!(builder.validate().is_ok());
+        assert!(builder.build().is_ok());
+
+        let builder = GlobWalkerBuilder::from_patterns(".", &["{a,b", "*.rs", "c}/{d"]);
+        let errors = builder.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].to_string().contains("`{a,b`"));
+        assert!(errors[1].to_string().contains("`c}/{d`"));
+        // The builder can still be used afterwards, failing on the first error.
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_from_env() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
+        create_dir_all(dir_path.join("target")).expect("Failed to create subfolders");
+        touch(&dir, &["a.rs", "b.toml", "c.md", "target[/]d.rs"][..]);
 
-        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let patterns = ["**/*.rs", "*.toml", "!target"].join(separator);
+        std::env::set_var("GLOBWALK_TEST_FROM_ENV", patterns);
+        let glob = GlobWalkerBuilder::from_env(dir_path, "GLOBWALK_TEST_FROM_ENV")
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = ["a.rs", "b.toml"].iter().map(ToString::to_string).collect();
+        equate_to_expected(glob, expected, dir_path);
 
-        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
+        let glob = GlobWalkerBuilder::from_env(dir_path, "GLOBWALK_TEST_FROM_ENV_MISSING")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(glob.count(), 0);
 
-        let g = GlobWalkerBuilder::new(dir_path, "*.{png,jpg,gif}")
+        std::env::set_var("GLOBWALK_TEST_FROM_ENV_INVALID", "{a,b");
+        assert!(GlobWalkerBuilder::from_env(dir_path, "GLOBWALK_TEST_FROM_ENV_INVALID").is_err());
+    }
+
+    #[test]
+    fn test_matched_contents_first() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a/b/c")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("a/skip")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("d")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "a[/]1.txt",
+                "a[/]b[/]2.txt",
+                "a[/]b[/]c[/]3.txt",
+                "a[/]skip[/]4.txt",
+                "d[/]5.txt",
+                "6.txt",
+            ][..],
+        );
+
+        let visited = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&visited);
+        let paths: Vec<_> = GlobWalkerBuilder::from_patterns(dir_path, &["**", "!skip"])
+            .min_depth(1)
+            .matched_contents_first(true)
+            .build()
+            .unwrap()
+            .log_misses(move |path, _| log.lock().unwrap().push(path.to_owned()))
+            .map(|e| e.unwrap().into_path())
+            .collect();
+        assert_eq!(paths.len(), 9);
+
+        // Every entry comes after everything below it, so deleting them in order always works.
+        for (i, path) in paths.iter().enumerate() {
+            assert!(!paths[i + 1..]
+                .iter()
+                .any(|p| p.starts_with(path) && p != path));
+        }
+        // Excluded directories are still not descended into.
+        let visited = visited.lock().unwrap();
+        assert!(!visited.iter().any(|p| p.ends_with("4.txt")));
+    }
+
+    #[test]
+    fn test_glob_escape_round_trip() {
+        let alphabet: Vec<char> = "*?[]{}\\!# \t\u{3000},-a.\u{e9}/".chars().collect();
+        // A small linear congruential generator, so that failures are reproducible.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 33) as usize
+        };
+
+        for _ in 0..2000 {
+            let len = next() % 8 + 1;
+            let s: String = (0..len)
+                .map(|_| alphabet[next() % alphabet.len()])
+                .collect();
+            let escaped = glob_escape(&s);
+            assert_eq!(glob_unescape(&escaped), s, "escaped as {:?}", escaped);
+
+            // Paths with empty or relative components, or with backslashes on Windows, are not in
+            // the form of the paths that are matched.
+            let unusual = |c: &str| matches!(c, "" | "." | "..");
+            if s.split('/').any(unusual) || (cfg!(windows) && s.contains('\\')) {
+                continue;
+            }
+            let mut builder = OverrideBuilder::new("/");
+            builder.add(&format!("/{}", escaped)).unwrap();
+            let matcher = builder.build().unwrap();
+            assert!(
+                matcher.matched(&s, false).is_whitelist(),
+                "{:?} is not matched by {:?}",
+                s,
+                escaped
+            );
+            assert!(!matcher.matched(format!("{}x", s), false).is_whitelist());
+        }
+    }
+
+    #[test]
+    fn test_glob_escape_walk() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        let mut names = vec!["a[1].txt", "{a,b}.txt", "!x", "#y", "a.txt", "b.txt"];
+        if cfg!(unix) {
+            names.extend(["*.txt", "?.txt", "back\\slash", "trailing "]);
+        }
+        touch(&dir, &names);
+
+        for name in &names {
+            let pattern = format!("/{}", glob_escape(name));
+            let matches: Vec<_> = GlobWalkerBuilder::new(dir_path, &pattern)
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_owned())
+                .collect();
+            assert_eq!(
+                matches,
+                [std::ffi::OsStr::new(name)],
+                "pattern {:?}",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_on_error() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["a.txt", "b.txt"][..]);
+        // The tests may run as root, which can read any directory, so use a broken link instead.
+        std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("broken")).unwrap();
+
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&errors);
+        let results: Vec<_> = GlobWalkerBuilder::new(dir_path, "*")
+            .follow_links(true)
+            .on_error(move |e| log.lock().unwrap().push(e))
+            .build()
+            .unwrap()
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_ok));
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), Some(dir_path.join("broken").as_path()));
+    }
+
+    #[test]
+    fn test_auto_ignore() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("logs")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &["a.txt", "b.log", "logs[/]c.log", "logs[/]d.txt"][..],
+        );
+        std::fs::write(
+            dir_path.join(".globwalkignore"),
+            "# Build logs\n!*.log\n\n  !d.txt  \n",
+        )
+        .unwrap();
+
+        let glob = GlobWalkerBuilder::from_patterns(dir_path, &["**", "*.log"])
+            .auto_ignore(true)
             .build()
             .unwrap();
+        let expected = ["a.txt", "logs"].iter().map(ToString::to_string).collect();
+        equate_to_expected(glob, expected, dir_path);
 
-        equate_to_expected(g, expected, dir_path);
+        let count = GlobWalkerBuilder::new(dir_path, "**")
+            .build()
+            .unwrap()
+            .filter_map(Result::ok)
+            .count();
+        // The base directory is not matched by `**`, but the ignore file is.
+        assert_eq!(count, 6);
     }
 
     #[test]
-    fn test_from_patterns() {
+    #[cfg(unix)]
+    fn test_owner_and_mode_filters() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("src/some_mod")).expect("");
-        create_dir_all(dir_path.join("tests")).expect("");
-        create_dir_all(dir_path.join("contrib")).expect("");
+        touch(&dir, &["plain", "setuid", "exec"][..]);
+        for (name, mode) in [("plain", 0o644), ("setuid", 0o4755), ("exec", 0o755)] {
+            std::fs::set_permissions(dir_path.join(name), std::fs::Permissions::from_mode(mode))
+                .expect("Failed to set permissions");
+        }
+        let metadata = std::fs::metadata(dir_path.join("plain")).unwrap();
+
+        let walk = |f: &dyn Fn(GlobWalkerBuilder) -> GlobWalkerBuilder| {
+            let mut names: Vec<_> = f(GlobWalkerBuilder::new(dir_path, "*"))
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(walk(&|b| b.with_mode_mask(0o4000, 0o4000)), ["setuid"]);
+        assert_eq!(
+            walk(&|b| b.with_mode_mask(0o111, 0o111)),
+            ["exec", "setuid"]
+        );
+        assert_eq!(walk(&|b| b.with_mode_mask(0o7777, 0o644)), ["plain"]);
+        assert_eq!(
+            walk(&|b| b.with_uid(metadata.uid()).with_gid(metadata.gid())),
+            ["exec", "plain", "setuid"]
+        );
+        assert!(walk(&|b| b.with_uid(metadata.uid().wrapping_add(1))).is_empty());
+        assert!(walk(&|b| b.with_gid(metadata.gid().wrapping_add(1))).is_empty());
+    }
 
+    #[test]
+    fn test_skip_dirs_larger_than() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("node_modules/pkg")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
         touch(
             &dir,
             &[
-                "a.rs",
-                "b.rs",
-                "avocado.rs",
-                "lib.c",
-                "src[/]hello.rs",
-                "src[/]world.rs",
-                "src[/]some_mod[/]unexpected.rs",
-                "src[/]cruel.txt",
-                "contrib[/]README.md",
-                "contrib[/]README.rst",
-                "contrib[/]lib.rs",
-            ][..],
+                "index.js",
+                "node_modules[/]a.js",
+                "node_modules[/]b.js",
+                "node_modules[/]pkg[/]c.js",
+                "src[/]main.js",
+                "src[/]util.js",
+            ],
         );
 
-        let expected: Vec<_> = [
-            "src[/]some_mod[/]unexpected.rs",
-            "src[/]world.rs",
-            "src[/]hello.rs",
-            "lib.c",
-            "contrib[/]lib.rs",
-            "contrib[/]README.md",
-            "contrib[/]README.rst",
-        ]
-        .iter()
-        .map(normalize_path_sep)
-        .collect();
+        let skipped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&skipped);
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.js")
+            .skip_dirs_larger_than(2)
+            .build()
+            .unwrap()
+            .log_misses(move |path, reason| {
+                if reason == SkipReason::LargeDir {
+                    log.lock().unwrap().push(path.to_owned());
+                }
+            });
+        let expected = ["index.js", "src[/]main.js", "src[/]util.js"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+        assert_eq!(*skipped.lock().unwrap(), [dir_path.join("node_modules")]);
+
+        // The limit is inclusive.
+        let count = GlobWalkerBuilder::new(dir_path, "**/*.js")
+            .skip_dirs_larger_than(3)
+            .build()
+            .unwrap()
+            .count();
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    fn test_match_info_pattern_index() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
+        touch(&dir, &["Cargo.toml", "src[/]lib.rs", "src[/]skip.rs"][..]);
+
+        let mut indices: Vec<_> =
+            GlobWalkerBuilder::from_patterns(dir_path, &["*.toml", "src/*.rs", "!skip.rs"])
+                .include_root(true)
+                .build()
+                .unwrap()
+                .with_match_info()
+                .map(Result::unwrap)
+                .map(|e| (e.entry.file_name().to_owned(), e.pattern_index()))
+                .collect();
+        indices.sort_by_key(|&(_, index)| index);
+
+        assert_eq!(
+            indices,
+            [
+                (dir_path.file_name().unwrap().to_owned(), None),
+                (OsString::from("Cargo.toml"), Some(0)),
+                (OsString::from("lib.rs"), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+
+        let builder = GlobWalkerBuilder::from_patterns(dir_path, &["*.rs", "!target"])
+            .skip_hidden(true)
+            .filter_entry(|_| true);
+        let debug = format!("{:?}", builder);
+        assert!(
+            debug.contains("patterns: [\"*.rs\", \"!target\"]"),
+            "{}",
+            debug
+        );
+        assert!(debug.contains("skip_hidden: true"), "{}", debug);
+
+        let debug = format!("{:?}", builder.build().unwrap());
+        assert!(
+            debug.contains("patterns: [\"*.rs\", \"!target\"]"),
+            "{}",
+            debug
+        );
+        assert!(debug.contains("walker: WalkDir { .. }"), "{}", debug);
+    }
+
+    #[test]
+    fn test_relative_depth() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src/bin/a/b")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "top.rs",
+                "src[/]lib.rs",
+                "src[/]bin[/]main.rs",
+                "src[/]bin[/]a[/]one.rs",
+                "src[/]bin[/]a[/]b[/]two.rs",
+            ],
+        );
+
+        let walk = |patterns: &[&str], f: &dyn Fn(GlobWalkerBuilder) -> GlobWalkerBuilder| {
+            let mut names: Vec<_> = f(GlobWalkerBuilder::from_patterns(dir_path, patterns))
+                .build()
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let pattern = &["src/bin/**/*.rs"];
+        assert_eq!(walk(pattern, &|b| b.max_depth_relative(1)), ["main.rs"]);
+        assert_eq!(
+            walk(pattern, &|b| b.min_depth_relative(2)),
+            ["one.rs", "two.rs"]
+        );
+        // Both the absolute and the relative bounds apply.
+        assert_eq!(
+            walk(pattern, &|b| b.max_depth_relative(5).max_depth(4)),
+            ["main.rs", "one.rs"]
+        );
+        // The shallowest anchor is used, and patterns without a slash are anchored in the base.
+        assert_eq!(
+            walk(&["src/**/*.rs", "src/bin/**/*.rs"], &|b| b
+                .max_depth_relative(1)),
+            ["lib.rs"]
+        );
+        assert_eq!(
+            walk(&["*.rs", "!src/bin/"], &|b| b.max_depth_relative(2)),
+            ["lib.rs", "top.rs"]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_and_resume() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a/b")).expect("Failed to create subfolders");
+        create_dir_all(dir_path.join("c")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &[
+                "1.txt",
+                "a[/]2.txt",
+                "a[/]b[/]3.txt",
+                "a[/]b[/]4.txt",
+                "a[/]5.txt",
+                "c[/]6.txt",
+            ],
+        );
+
+        for order in [TraversalOrder::DepthFirst, TraversalOrder::BreadthFirst] {
+            let builder = GlobWalkerBuilder::new(dir_path, "*.txt")
+                .sort_by_file_name()
+                .traversal_order(order);
+            let names = |walker: GlobWalker| -> Vec<_> {
+                walker
+                    .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                    .collect()
+            };
+            let all = names(builder.clone().build().unwrap());
+            assert_eq!(all.len(), 6);
+
+            let mut walker = builder.clone().build().unwrap();
+            assert_eq!(walker.checkpoint(), None);
+            let mut seen = Vec::new();
+            for _ in 0..3 {
+                seen.push(walker.next().unwrap().unwrap().file_name().to_owned());
+            }
+            let checkpoint = walker.checkpoint().unwrap();
+            let rest = names(builder.clone().resume_from(checkpoint).build().unwrap());
+            assert_eq!(seen.len() + rest.len(), all.len());
+            assert_eq!(rest[..], all[3..]);
+        }
+
+        assert!(GlobWalkerBuilder::new(dir_path, "*.txt")
+            .with_thread_count(2)
+            .resume_from(WalkCheckpoint {
+                path: PathBuf::from("a"),
+            })
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_checkpoint_serde() {
+        let checkpoint = WalkCheckpoint {
+            path: ["a", "b.txt"].iter().collect(),
+        };
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        let parsed: WalkCheckpoint = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, checkpoint);
+    }
+
+    #[test]
+    fn test_patterns_accessors() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        let patterns = ["*.rs", "!target", "docs/**/*.md"];
+
+        let walker = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .build()
+            .unwrap();
+        assert_eq!(walker.pattern_count(), patterns.len());
+        assert_eq!(walker.patterns(), patterns);
+
+        let walker = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+            .add_pattern_with_priority("!build.rs", 10)
+            .add_pattern_with_priority("*.toml", -1)
+            .build()
+            .unwrap();
+        assert_eq!(walker.pattern_count(), 5);
+        assert_eq!(
+            walker.patterns(),
+            ["*.rs", "!target", "docs/**/*.md", "!build.rs", "*.toml"]
+        );
+        touch(&dir, &["Cargo.toml"][..]);
+        let walker = GlobWalkerBuilder::from_patterns(dir_path, &["*.rs", "*"])
+            .add_pattern_with_priority("*.toml", -1)
+            .build()
+            .unwrap();
+        assert_eq!(walker.patterns(), ["*.rs", "*", "*.toml"]);
+        let indices: Vec<_> = walker
+            .with_match_info()
+            .map(|e| e.unwrap().pattern_index())
+            .collect();
+        assert_eq!(indices, [Some(1)]);
+
+        let walker = GlobWalkerBuilder::new(dir_path, "./src/*.rs")
+            .resolve_patterns_relative_to(dir_path)
+            .build()
+            .unwrap();
+        assert_eq!(walker.patterns(), ["./src/*.rs"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_follow_links_max_depth() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        let targets = TempDir::new().expect("Failed to create temporary folder");
+        for (name, next) in [("t1", Some("t2")), ("t2", Some("t3")), ("t3", None)] {
+            let target = targets.path().join(name);
+            create_dir_all(&target).expect("Failed to create subfolders");
+            File::create(target.join(format!("{}.txt", name))).expect("Failed to create a file");
+            if let Some(next) = next {
+                symlink(targets.path().join(next), target.join("link"))
+                    .expect("Failed to create a link");
+            }
+        }
+        touch(&dir, &["top.txt"][..]);
+        symlink(targets.path().join("t1"), dir_path.join("link")).expect("Failed to create a link");
+
+        let walk = |n| {
+            let mut paths: Vec<_> = GlobWalkerBuilder::new(dir_path, "**")
+                .follow_links_max_depth(n)
+                .build()
+                .unwrap()
+                .map(|e| {
+                    let e = e.unwrap();
+                    normalize_path_sep(e.path().strip_prefix(dir_path).unwrap().to_str().unwrap())
+                })
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(walk(0), ["link", "top.txt"]);
+        assert_eq!(walk(1), ["link", "link/link", "link/t1.txt", "top.txt"]);
+        assert_eq!(
+            walk(2),
+            [
+                "link",
+                "link/link",
+                "link/link/link",
+                "link/link/t2.txt",
+                "link/t1.txt",
+                "top.txt"
+            ]
+        );
+        assert_eq!(walk(3).len(), 7);
+    }
+
+    #[test]
+    fn test_max_open_dirs() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("a/b/c")).expect("Failed to create subfolders");
+        touch(
+            &dir,
+            &["a[/]1.txt", "a[/]b[/]2.txt", "a[/]b[/]c[/]3.txt"][..],
+        );
+
+        assert!(GlobWalkerBuilder::new(dir_path, "*.txt")
+            .with_max_open_dirs(0)
+            .is_err());
+        let glob = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .with_max_open_dirs(1)
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = ["a[/]1.txt", "a[/]b[/]2.txt", "a[/]b[/]c[/]3.txt"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
+        equate_to_expected(glob, expected, dir_path);
+
+        let config = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .with_fd_limit_from_system()
+            .to_config();
+        assert!(config.max_open.is_some_and(|n| n >= 1));
+    }
 
-        let patterns = ["src/**/*.rs", "*.c", "**/lib.rs", "**/*.{md,rst}"];
-        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
-            .build()
-            .unwrap();
+    #[test]
+    fn test_glob_error_source() {
+        use std::error::Error;
 
-        equate_to_expected(glob, expected, dir_path);
+        let error = GlobWalkerBuilder::new(".", "{a,b")
+            .build()
+            .expect_err("pattern should be rejected");
+        let source = error
+            .source()
+            .expect("the error of `ignore` should be its source");
+        assert!(source.is::<ignore::Error>());
     }
 
     #[test]
-    fn test_case_insensitive_matching() {
+    fn test_require_base_exists() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("src/some_mod")).expect("");
-        create_dir_all(dir_path.join("tests")).expect("");
-        create_dir_all(dir_path.join("contrib")).expect("");
+        touch(&dir, &["file.txt"][..]);
 
-        touch(
-            &dir,
-            &[
-                "a.rs",
-                "b.rs",
-                "avocado.RS",
-                "lib.c",
-                "src[/]hello.RS",
-                "src[/]world.RS",
-                "src[/]some_mod[/]unexpected.rs",
-                "src[/]cruel.txt",
-                "contrib[/]README.md",
-                "contrib[/]README.rst",
-                "contrib[/]lib.rs",
-            ][..],
-        );
+        for base in [dir_path.join("missing"), dir_path.join("file.txt")] {
+            let error = GlobWalkerBuilder::new(&base, "*")
+                .require_base_exists(true)
+                .allow_empty_base(true)
+                .build()
+                .expect_err("the base directory should be required");
+            let error = std::io::Error::from(error);
+            assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        }
 
-        let expected: Vec<_> = [
-            "src[/]some_mod[/]unexpected.rs",
-            "src[/]hello.RS",
-            "src[/]world.RS",
-            "lib.c",
-            "contrib[/]lib.rs",
-            "contrib[/]README.md",
-            "contrib[/]README.rst",
-        ]
-        .iter()
-        .map(normalize_path_sep)
-        .collect();
+        assert!(GlobWalkerBuilder::new(dir_path, "*")
+            .require_base_exists(true)
+            .build()
+            .is_ok());
+    }
 
-        let patterns = ["src/**/*.rs", "*.c", "**/lib.rs", "**/*.{md,rst}"];
-        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
-            .case_insensitive(true)
+    #[test]
+    #[cfg(unix)]
+    fn test_write_json_paths() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        touch(&dir, &["plain.txt", "quote\"back\\slash\ttab.txt"][..]);
+        std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join("broken.txt"))
+            .expect("Failed to create a link");
+
+        let mut output = Vec::new();
+        let count = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .follow_links(true)
             .build()
+            .unwrap()
+            .write_json_paths(&mut output)
             .unwrap();
+        assert_eq!(count, 2);
 
-        equate_to_expected(glob, expected, dir_path);
+        let output = String::from_utf8(output).unwrap();
+        let mut lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        lines.sort_by_key(|value| value.to_string());
+        let path = |name: &str| serde_json::Value::from(dir_path.join(name).to_str().unwrap());
+        assert_eq!(
+            lines,
+            [
+                path("plain.txt"),
+                path("quote\"back\\slash\ttab.txt"),
+                serde_json::Value::Null,
+            ]
+        );
     }
 
     #[test]
-    fn test_match_dir() {
+    #[cfg(unix)]
+    fn test_deny_symlinks() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("mod")).expect("");
-
-        touch(
-            &dir,
-            &[
-                "a.png",
-                "b.png",
-                "c.png",
-                "mod[/]a.png",
-                "mod[/]b.png",
-                "mod[/]c.png",
-            ][..],
-        );
+        create_dir_all(dir_path.join("sub")).expect("Failed to create subfolders");
+        touch(&dir, &["a.txt", "sub[/]b.txt"][..]);
 
-        let expected: Vec<_> = ["mod"].iter().map(normalize_path_sep).collect();
-        let glob = GlobWalkerBuilder::new(dir_path, "mod").build().unwrap();
+        let count = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .deny_symlinks(true)
+            .build()
+            .unwrap()
+            .count();
+        assert_eq!(count, 2);
 
-        equate_to_expected(glob, expected, dir_path);
+        let link = dir_path.join("sub").join("link");
+        std::os::unix::fs::symlink(dir_path.join("a.txt"), &link).expect("Failed to create a link");
+        for follow in [false, true] {
+            let results: Vec<_> = GlobWalkerBuilder::new(dir_path, "*.md")
+                .deny_symlinks(true)
+                .follow_links(follow)
+                .build()
+                .unwrap()
+                .collect();
+            assert_eq!(results.len(), 1);
+            let err = results.into_iter().next().unwrap().unwrap_err();
+            assert_eq!(err.path(), Some(link.as_path()));
+            let err = std::io::Error::from(err);
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(
+                err.to_string(),
+                format!("{} is a symbolic link", link.display())
+            );
+        }
     }
 
     #[test]
-    fn test_blacklist() {
+    #[cfg(target_os = "linux")]
+    fn test_same_file_system() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("src/some_mod")).expect("");
-        create_dir_all(dir_path.join("tests")).expect("");
-        create_dir_all(dir_path.join("contrib")).expect("");
+        let proc_dev = device_id_of("/proc");
+        if proc_dev.is_err() || proc_dev.ok() == device_id_of(dir_path).ok() {
+            // `/proc` is needed as a file system distinct from the temporary folder.
+            return;
+        }
+        touch(&dir, &["a.txt"][..]);
+        std::os::unix::fs::symlink("/proc", dir_path.join("proc"))
+            .expect("Failed to create a link");
+
+        let walk = |same_file_system| {
+            GlobWalkerBuilder::new(dir_path, "**")
+                .follow_links(true)
+                .same_file_system(same_file_system)
+                .max_depth(2)
+                .build()
+                .unwrap()
+                .filter_map(Result::ok)
+                .count()
+        };
 
+        // `a.txt` and the link, whose contents are not visited.
+        assert_eq!(walk(true), 2);
+        assert!(walk(false) > 2);
+    }
+
+    #[test]
+    fn test_into_grouped() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("src")).expect("Failed to create subfolders");
         touch(
             &dir,
             &[
-                "a.rs",
-                "b.rs",
-                "avocado.rs",
-                "lib.c",
-                "src[/]hello.rs",
-                "src[/]world.rs",
-                "src[/]some_mod[/]unexpected.rs",
-                "src[/]cruel.txt",
-                "contrib[/]README.md",
-                "contrib[/]README.rst",
-                "contrib[/]lib.rs",
-            ][..],
+                "Cargo.toml",
+                "build.rs",
+                "skip.rs",
+                "src[/]lib.rs",
+                "src[/]main.rs",
+                "README.md",
+            ],
         );
 
-        let expected: Vec<_> = [
-            "src[/]some_mod[/]unexpected.rs",
-            "src[/]hello.rs",
-            "lib.c",
-            "contrib[/]lib.rs",
-            "contrib[/]README.md",
-            "contrib[/]README.rst",
-        ]
-        .iter()
-        .map(normalize_path_sep)
-        .collect();
-
-        let patterns = [
-            "src/**/*.rs",
-            "*.c",
-            "**/lib.rs",
-            "**/*.{md,rst}",
-            "!world.rs",
-        ];
-
-        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+        let groups = GlobWalkerBuilder::from_patterns(dir_path, &["*", "**/*.rs", "!skip.rs"])
+            .file_type(FileType::FILE)
             .build()
-            .unwrap();
+            .unwrap()
+            .into_grouped();
 
-        equate_to_expected(glob, expected, dir_path);
+        let names = |pattern: &str| {
+            let mut names: Vec<_> = groups[pattern]
+                .iter()
+                .map(|e| e.file_name().to_str().unwrap().to_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(groups.len(), 2);
+        assert_eq!(names("*"), ["Cargo.toml", "README.md"]);
+        assert_eq!(names("**/*.rs"), ["build.rs", "lib.rs", "main.rs"]);
     }
 
     #[test]
-    fn test_blacklist_dir() {
+    fn test_merge() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("Pictures")).expect("");
-
+        create_dir_all(dir_path.join("a/b")).expect("Failed to create subfolders");
         touch(
             &dir,
             &[
-                "a.png",
-                "b.png",
-                "c.png",
-                "Pictures[/]a.png",
-                "Pictures[/]b.png",
-                "Pictures[/]c.png",
-            ][..],
+                "main.rs",
+                "Cargo.toml",
+                "README.md",
+                "a[/]lib.rs",
+                "a[/]b[/]deep.toml",
+            ],
         );
 
-        let expected: Vec<_> = ["a.png", "b.png", "c.png"]
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .merge(GlobWalkerBuilder::new(dir_path, "**/*.toml"))
+            .unwrap()
+            .build()
+            .unwrap();
+        let expected = ["main.rs", "Cargo.toml", "a[/]lib.rs", "a[/]b[/]deep.toml"]
             .iter()
             .map(normalize_path_sep)
             .collect();
+        equate_to_expected(glob, expected, dir_path);
 
-        let patterns = ["*.{png,jpg,gif}", "!Pictures"];
-        let glob = GlobWalkerBuilder::from_patterns(dir_path, &patterns)
+        let glob = GlobWalkerBuilder::new(dir_path, "**/*.rs")
+            .max_depth(3)
+            .merge(
+                GlobWalkerBuilder::new(dir_path, "**/*.toml")
+                    .min_depth(1)
+                    .max_depth(2),
+            )
+            .unwrap()
             .build()
             .unwrap();
-
+        let expected = ["main.rs", "Cargo.toml", "a[/]lib.rs"]
+            .iter()
+            .map(normalize_path_sep)
+            .collect();
         equate_to_expected(glob, expected, dir_path);
+
+        assert!(GlobWalkerBuilder::new(dir_path, "*.rs")
+            .merge(GlobWalkerBuilder::new(dir_path.join("a"), "*.toml"))
+            .is_err());
     }
 
     #[test]
-    fn test_glob_with_double_star_pattern() {
+    #[cfg(unix)]
+    fn test_deduplicate_inodes() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
-        let dir_path = dir.path().canonicalize().unwrap();
-
-        touch(&dir, &["a.rs", "a.jpg", "a.png", "b.docx"][..]);
+        let dir_path = dir.path();
+        create_dir_all(dir_path.join("sub")).expect("Failed to create subfolders");
+        touch(&dir, &["a.txt", "other.txt"][..]);
+        std::fs::hard_link(dir_path.join("a.txt"), dir_path.join("sub").join("b.txt"))
+            .expect("Failed to create a hard link");
 
-        let expected = ["a.jpg", "a.png"].iter().map(ToString::to_string).collect();
-        let mut cwd = dir_path.clone();
-        cwd.push("**");
-        cwd.push("*.{png,jpg,gif}");
-        let glob = glob(cwd.to_str().unwrap().to_owned()).unwrap();
-        equate_to_expected(glob, expected, &dir_path);
+        for threads in [1, 3] {
+            let walk = |yes| {
+                GlobWalkerBuilder::new(dir_path, "**/*.txt")
+                    .deduplicate_inodes(yes)
+                    .with_thread_count(threads)
+                    .build()
+                    .unwrap()
+                    .map(|e| e.unwrap().file_name().to_str().unwrap().to_owned())
+                    .filter(|name| name != "other.txt")
+                    .count()
+            };
+            assert_eq!(walk(false), 2);
+            assert_eq!(walk(true), 1);
+        }
     }
 
     #[test]
-    fn test_glob_single_star() {
+    fn test_peekable_glob() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("Pictures")).expect("");
-        create_dir_all(dir_path.join("Pictures").join("b")).expect("");
+        touch(&dir, &["a.rs", "b.rs", "c.txt"][..]);
 
-        touch(
-            &dir,
-            &[
-                "a.png",
-                "b.png",
-                "c.png",
-                "Pictures[/]a.png",
-                "Pictures[/]b.png",
-                "Pictures[/]c.png",
-                "Pictures[/]b[/]c.png",
-                "Pictures[/]b[/]c.png",
-                "Pictures[/]b[/]c.png",
-            ][..],
+        let mut walker = GlobWalkerBuilder::new(dir_path, "*.rs")
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .build()
+            .unwrap()
+            .peekable_glob();
+
+        let first = walker.peek().unwrap().as_ref().unwrap().path().to_owned();
+        let again = walker.peek().unwrap().as_ref().unwrap().path().to_owned();
+        assert_eq!(first, again);
+        assert_eq!(first, dir_path.join("a.rs"));
+        assert_eq!(walker.size_hint().0, 1);
+
+        assert_eq!(walker.next().unwrap().unwrap().path(), first);
+        assert_eq!(
+            walker.peek().unwrap().as_ref().unwrap().path(),
+            dir_path.join("b.rs")
+        );
+        assert_eq!(
+            walker.next().unwrap().unwrap().path(),
+            dir_path.join("b.rs")
         );
+        assert!(walker.peek().is_none());
+        assert!(walker.next().is_none());
+    }
 
-        let glob = GlobWalkerBuilder::new(dir_path, "*")
-            .sort_by(|a, b| a.path().cmp(b.path()))
+    #[test]
+    fn test_walk_root_entry() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let root = dir.path().join("mod");
+        create_dir_all(root.join("mod")).expect("Failed to create subfolders");
+        File::create(root.join("lib.rs")).expect("Failed to create file");
+
+        let walk = |builder: GlobWalkerBuilder| {
+            let mut paths: Vec<_> = builder
+                .build()
+                .unwrap()
+                .map(|e| {
+                    let e = e.unwrap();
+                    (e.path().to_owned(), e.depth())
+                })
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            walk(GlobWalkerBuilder::new(&root, "mod")),
+            [(root.join("mod"), 1)]
+        );
+        assert_eq!(
+            walk(GlobWalkerBuilder::new(&root, "mod").with_walk_root_entry(true)),
+            [(root.clone(), 0), (root.join("mod"), 1)]
+        );
+        assert_eq!(
+            walk(GlobWalkerBuilder::new(&root, "*.rs").with_walk_root_entry(true)),
+            [(root.join("lib.rs"), 1)]
+        );
+        assert_eq!(
+            walk(
+                GlobWalkerBuilder::new(&root, "mod")
+                    .with_walk_root_entry(true)
+                    .file_type(FileType::FILE)
+            ),
+            []
+        );
+
+        let index: Vec<_> = GlobWalkerBuilder::from_patterns(&root, &["*.rs", "mod"])
+            .with_walk_root_entry(true)
+            .max_depth(0)
             .build()
-            .unwrap();
-        let expected = ["Pictures", "a.png", "b.png", "c.png"]
-            .iter()
-            .map(ToString::to_string)
+            .unwrap()
+            .with_match_info()
+            .map(|e| e.unwrap().pattern_index())
             .collect();
-        equate_to_expected(glob, expected, dir_path);
+        assert_eq!(index, [Some(1)]);
     }
 
     #[test]
-    fn test_file_type() {
+    fn test_auto_case_sensitivity() {
         let dir = TempDir::new().expect("Failed to create temporary folder");
         let dir_path = dir.path();
-        create_dir_all(dir_path.join("Pictures")).expect("");
-        create_dir_all(dir_path.join("Pictures").join("b")).expect("");
+        touch(&dir, &["a.rs", "b.txt"][..]);
 
-        touch(
-            &dir,
-            &[
-                "a.png",
-                "b.png",
-                "c.png",
-                "Pictures[/]a.png",
-                "Pictures[/]b.png",
-                "Pictures[/]c.png",
-                "Pictures[/]b[/]c.png",
-                "Pictures[/]b[/]c.png",
-                "Pictures[/]b[/]c.png",
-            ][..],
-        );
+        let detected = is_case_insensitive(dir_path).unwrap();
+        assert_eq!(detected, dir_path.join("A.RS").exists());
 
-        let glob = GlobWalkerBuilder::new(dir_path, "*")
-            .sort_by(|a, b| a.path().cmp(b.path()))
-            .file_type(FileType::DIR)
+        let matches = |builder: GlobWalkerBuilder| builder.build().unwrap().count();
+        for configured in [false, true] {
+            assert_eq!(
+                matches(
+                    GlobWalkerBuilder::new(dir_path, "*.RS")
+                        .case_insensitive(configured)
+                        .auto_case_sensitivity(true)
+                ),
+                usize::from(detected)
+            );
+        }
+
+        // Without any entry to probe, the configured setting is used instead.
+        let empty = TempDir::new().expect("Failed to create temporary folder");
+        assert_eq!(is_case_insensitive(empty.path()), None);
+        for configured in [false, true] {
+            let builder = GlobWalkerBuilder::new(empty.path(), "*")
+                .case_insensitive(configured)
+                .auto_case_sensitivity(true);
+            assert_eq!(builder.build().unwrap().case_insensitive, configured);
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_auto_case_sensitivity_windows() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        touch(&dir, &["Main.rs"][..]);
+
+        let walker = GlobWalkerBuilder::new(dir.path(), "main.RS")
+            .auto_case_sensitivity(true)
             .build()
             .unwrap();
-        let expected = ["Pictures"].iter().map(ToString::to_string).collect();
-        equate_to_expected(glob, expected, dir_path);
+        assert_eq!(walker.count(), 1);
+    }
 
-        let glob = GlobWalkerBuilder::new(dir_path, "*")
-            .sort_by(|a, b| a.path().cmp(b.path()))
-            .file_type(FileType::FILE)
+    #[test]
+    #[cfg(unix)]
+    fn test_deny_symlinks_threaded() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        for i in 0..16 {
+            let sub = dir_path.join(format!("sub{}", i));
+            create_dir_all(&sub).expect("Failed to create subfolders");
+            for j in 0..64 {
+                File::create(sub.join(format!("{}.txt", j))).expect("Failed to create file");
+            }
+        }
+        let link = dir_path.join("sub0").join("link");
+        std::os::unix::fs::symlink(dir_path.join("sub1"), &link).expect("Failed to create a link");
+
+        let mut walker = GlobWalkerBuilder::new(dir_path, "*.txt")
+            .deny_symlinks(true)
+            .with_thread_count(4)
             .build()
             .unwrap();
-        let expected = ["a.png", "b.png", "c.png"]
-            .iter()
-            .map(ToString::to_string)
+        let err = walker.by_ref().find_map(Result::err).unwrap();
+        assert_eq!(err.path(), Some(link.as_path()));
+        assert!(walker.next().is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_timeout_with_errors_only() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        for i in 0..100 {
+            std::os::unix::fs::symlink(dir_path.join("missing"), dir_path.join(i.to_string()))
+                .expect("Failed to create a link");
+        }
+
+        let results: Vec<_> = GlobWalkerBuilder::new(dir_path, "*")
+            .follow_links(true)
+            .timeout(Duration::from_nanos(1))
+            .build()
+            .unwrap()
             .collect();
-        equate_to_expected(glob, expected, dir_path);
+        assert_eq!(results.len(), 1);
+        let err = results[0].as_ref().unwrap_err();
+        assert_eq!(
+            err.io_error().map(std::io::Error::kind),
+            Some(std::io::ErrorKind::TimedOut)
+        );
+    }
+
+    #[test]
+    fn test_timeout_threaded() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        let dir_path = dir.path();
+        for i in 0..20 {
+            let sub = dir_path.join(format!("dir{}", i));
+            create_dir_all(&sub).expect("Failed to create subfolders");
+            for j in 0..50 {
+                File::create(sub.join(format!("{}.txt", j))).expect("Failed to create a test file");
+            }
+        }
+
+        let results: Vec<_> = GlobWalkerBuilder::new(dir_path, "**/*.txt")
+            .timeout(Duration::from_nanos(1))
+            .with_thread_count(4)
+            .build()
+            .unwrap()
+            .collect();
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+        let err = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(
+            err.io_error().map(std::io::Error::kind),
+            Some(std::io::ErrorKind::TimedOut)
+        );
     }
 }
@@ -0,0 +1,158 @@
+// Copyright (c) 2017 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Walking several base directories with the same patterns.
+
+#[cfg(feature = "rayon")]
+use crate::ParallelGlobWalker;
+use crate::{DirEntry, GlobError, GlobWalker, GlobWalkerBuilder, WalkError};
+
+/// A builder for walking several base directories with the same patterns.
+///
+/// This is constructed by [`GlobWalkerBuilder::from_patterns_multi_base`], and holds one
+/// `GlobWalkerBuilder` per base directory.
+///
+/// [`GlobWalkerBuilder::from_patterns_multi_base`]: struct.GlobWalkerBuilder.html#method.from_patterns_multi_base
+pub struct GlobWalkerMultiBuilder {
+    pub(crate) builders: Vec<GlobWalkerBuilder>,
+}
+
+impl GlobWalkerMultiBuilder {
+    /// Apply the same settings to the builder of every base directory.
+    ///
+    /// ```rust
+    /// # include!("doctests.rs");
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// # let temp_dir = create_files(&["a/main.rs", "b/lib.rs", "b/nested/mod.rs"])?;
+    /// # let BASE_DIR = temp_dir.path();
+    /// let bases = [BASE_DIR.join("a"), BASE_DIR.join("b")];
+    /// let walker = globwalk::GlobWalkerBuilder::from_patterns_multi_base(&bases, &["*.rs"])
+    ///     .configure(|builder| builder.max_depth(1))
+    ///     .build()?;
+    /// assert_eq!(walker.count(), 2);
+    /// # Ok(()) }
+    /// # fn main() { run().unwrap() }
+    /// ```
+    #[must_use = "builder methods must be chained"]
+    pub fn configure<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(GlobWalkerBuilder) -> GlobWalkerBuilder,
+    {
+        self.builders = self.builders.into_iter().map(f).collect();
+        self
+    }
+
+    /// Finalize and build a `GlobWalkerMulti` instance.
+    pub fn build(self) -> Result<GlobWalkerMulti, GlobError> {
+        let walkers = self
+            .builders
+            .into_iter()
+            .map(GlobWalkerBuilder::build)
+            .collect::<Result<_, _>>()?;
+        Ok(GlobWalkerMulti { walkers, next: 0 })
+    }
+
+    /// Finalize and build a parallel iterator over the matches of all the base directories.
+    ///
+    /// Every base directory is split between threads as in
+    /// [`GlobWalkerBuilder::build_parallel`].
+    ///
+    /// [`GlobWalkerBuilder::build_parallel`]: struct.GlobWalkerBuilder.html#method.build_parallel
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(self) -> Result<ParallelGlobWalker, GlobError> {
+        let mut shards = Vec::new();
+        for builder in self.builders {
+            shards.extend(builder.build_walkers(Some(rayon::current_num_threads()))?);
+        }
+        Ok(ParallelGlobWalker { shards })
+    }
+}
+
+/// An iterator over the matches of several base directories.
+///
+/// The walkers of the base directories are advanced in turn, so that entries from every base are
+/// yielded early on, rather than one base after the other. As with a single base, the paths of
+/// the entries start with the base directory they were found in.
+///
+/// This is constructed by [`GlobWalkerMultiBuilder::build`].
+///
+/// [`GlobWalkerMultiBuilder::build`]: struct.GlobWalkerMultiBuilder.html#method.build
+pub struct GlobWalkerMulti {
+    walkers: Vec<GlobWalker>,
+    /// The index of the walker to advance next.
+    next: usize,
+}
+
+impl Iterator for GlobWalkerMulti {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.walkers.is_empty() {
+            let index = self.next % self.walkers.len();
+            match self.walkers[index].next() {
+                Some(item) => {
+                    self.next = index + 1;
+                    return Some(item);
+                }
+                // The next walker takes the place of the exhausted one.
+                None => {
+                    self.walkers.remove(index);
+                    self.next = index;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl std::iter::FusedIterator for GlobWalkerMulti {}
+
+#[cfg(test)]
+mod tests {
+    use crate::GlobWalkerBuilder;
+    use std::fs::{create_dir_all, File};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_multi_base() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        for name in &["a/1.rs", "a/2.rs", "a/3.rs", "b/4.rs", "b/5.txt", "c/6.rs"] {
+            let path = dir.path().join(name);
+            create_dir_all(path.parent().unwrap()).expect("Failed to create subfolders");
+            File::create(path).expect("Failed to create a test file");
+        }
+
+        let bases = ["a", "b", "c", "missing"].map(|base| dir.path().join(base));
+        let results: Vec<_> = GlobWalkerBuilder::from_patterns_multi_base(&bases, &["*.rs"])
+            .configure(|builder| builder.sort_by_file_name())
+            .build()
+            .unwrap()
+            .collect();
+        let names: Vec<_> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|e| e.path().strip_prefix(dir.path()).unwrap().to_owned())
+            .collect();
+        let expected = ["a/1.rs", "b/4.rs", "c/6.rs", "a/2.rs", "a/3.rs"];
+        assert_eq!(names, expected.map(std::path::PathBuf::from));
+        // The missing base directory is reported like in a single-base walk.
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+}
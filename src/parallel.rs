@@ -0,0 +1,149 @@
+// Copyright (c) 2017 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Integration with `rayon`.
+
+use crate::{DirEntry, GlobWalker, GlobWalkerResult, WalkError};
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::iter::{Either, FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
+/// A parallel iterator over the matches of a walk.
+///
+/// This is constructed by [`GlobWalkerBuilder::build_parallel`].
+///
+/// [`GlobWalkerBuilder::build_parallel`]: struct.GlobWalkerBuilder.html#method.build_parallel
+pub struct ParallelGlobWalker {
+    pub(crate) shards: Vec<GlobWalker>,
+}
+
+impl ParallelIterator for ParallelGlobWalker {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.shards
+            .into_par_iter()
+            .flat_map_iter(|walker| walker)
+            .drive_unindexed(consumer)
+    }
+}
+
+impl FromParallelIterator<Result<DirEntry, WalkError>> for GlobWalkerResult {
+    fn from_par_iter<I>(iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = Result<DirEntry, WalkError>>,
+    {
+        let (entries, errors) = iter.into_par_iter().partition_map(|r| match r {
+            Ok(e) => Either::Left(e),
+            Err(e) => Either::Right(e),
+        });
+        GlobWalkerResult { entries, errors }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlobWalkerBuilder, GlobWalkerResult};
+    use rayon::iter::{ParallelBridge, ParallelIterator};
+    use std::collections::BTreeSet;
+    use std::fs::{create_dir_all, File};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_par_bridge() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        for name in &["a.rs", "b.rs", "c.rs", "d.txt"] {
+            File::create(dir.path().join(name)).expect("Failed to create a test file");
+        }
+
+        let result: GlobWalkerResult = GlobWalkerBuilder::new(dir.path(), "*.rs")
+            .build()
+            .unwrap()
+            .par_bridge()
+            .collect();
+        assert_eq!(result.entries.len(), 3);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn test_build_parallel() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        for i in 0..8 {
+            let sub = dir.path().join(format!("mod{}", i)).join("inner");
+            create_dir_all(&sub).expect("Failed to create subfolders");
+            for name in &["a.rs", "b.txt"] {
+                File::create(sub.join(name)).expect("Failed to create a test file");
+                File::create(sub.parent().unwrap().join(name))
+                    .expect("Failed to create a test file");
+            }
+        }
+        File::create(dir.path().join("top.rs")).expect("Failed to create a test file");
+
+        let builder = || {
+            GlobWalkerBuilder::from_patterns(dir.path(), &["**/*.rs", "!mod3"])
+                .min_depth(1)
+                .max_depth(2)
+        };
+        let sequential: BTreeSet<_> = builder()
+            .build()
+            .unwrap()
+            .map(|e| e.unwrap().into_path())
+            .collect();
+        let parallel: BTreeSet<_> = builder()
+            .build_parallel()
+            .unwrap()
+            .map(|e| e.unwrap().into_path())
+            .collect();
+        assert_eq!(sequential.len(), 8);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn test_build_parallel_contents_first() {
+        let dir = TempDir::new().expect("Failed to create temporary folder");
+        for i in 0..6 {
+            let sub = dir.path().join(format!("mod{}", i));
+            create_dir_all(&sub).expect("Failed to create subfolders");
+            File::create(sub.join("a.rs")).expect("Failed to create a test file");
+        }
+
+        let builder = || GlobWalkerBuilder::new(dir.path(), "**/*.rs").contents_first(true);
+        let sequential: BTreeSet<_> = builder()
+            .build()
+            .unwrap()
+            .map(|e| e.unwrap().into_path())
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let parallel: BTreeSet<_> = pool.install(|| {
+            builder()
+                .build_parallel()
+                .unwrap()
+                .map(|e| e.unwrap().into_path())
+                .collect()
+        });
+        assert_eq!(sequential.len(), 6);
+        assert_eq!(parallel, sequential);
+    }
+}
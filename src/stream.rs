@@ -0,0 +1,59 @@
+// Copyright (c) 2017 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Integration with `tokio`.
+
+use crate::{DirEntry, GlobWalker, WalkError};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// A stream of the matches of a walk, which runs on the blocking thread pool of `tokio`.
+///
+/// This is constructed by [`GlobWalkerBuilder::build_async`]. Dropping the stream stops the walk
+/// shortly after, once the entry being matched has been sent.
+///
+/// [`GlobWalkerBuilder::build_async`]: struct.GlobWalkerBuilder.html#method.build_async
+pub struct GlobWalkerStream {
+    receiver: mpsc::Receiver<Result<DirEntry, WalkError>>,
+}
+
+impl GlobWalkerStream {
+    pub(crate) fn spawn(walker: GlobWalker) -> Self {
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::task::spawn_blocking(move || {
+            for item in walker {
+                // The stream was dropped, so nobody is interested anymore.
+                if sender.blocking_send(item).is_err() {
+                    break;
+                }
+            }
+        });
+        GlobWalkerStream { receiver }
+    }
+}
+
+impl futures::Stream for GlobWalkerStream {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright (c) 2017 Gilad Naaman
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The directory traversals backing `GlobWalker`.
+
+use crate::DirEntry;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+type SortFn = Arc<Mutex<dyn FnMut(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static>>;
+type TweakFn = Arc<dyn Fn(WalkDir) -> WalkDir + Send + Sync + 'static>;
+
+/// The `WalkDir` settings configured through `GlobWalkerBuilder`.
+///
+/// These are stored separately, rather than in a `WalkDir` instance, so that several walkers can
+/// be created from the same configuration.
+#[derive(Clone)]
+pub(crate) struct WalkDirConfig {
+    pub(crate) min_depth: usize,
+    pub(crate) max_depth: usize,
+    pub(crate) follow_links: bool,
+    pub(crate) max_open: Option<usize>,
+    pub(crate) sort_by: Option<SortFn>,
+    pub(crate) contents_first: bool,
+    pub(crate) same_file_system: bool,
+    pub(crate) tweaks: Vec<TweakFn>,
+}
+
+impl Default for WalkDirConfig {
+    fn default() -> Self {
+        WalkDirConfig {
+            min_depth: 0,
+            max_depth: usize::MAX,
+            follow_links: false,
+            max_open: None,
+            sort_by: None,
+            contents_first: false,
+            same_file_system: false,
+            tweaks: Vec::new(),
+        }
+    }
+}
+
+impl WalkDirConfig {
+    /// Mirrors `WalkDir::min_depth`, which also raises the maximum depth if needed.
+    pub(crate) fn set_min_depth(&mut self, depth: usize) {
+        self.min_depth = depth;
+        if self.min_depth > self.max_depth {
+            self.max_depth = self.min_depth;
+        }
+    }
+
+    /// Mirrors `WalkDir::max_depth`, which also lowers the minimum depth if needed.
+    pub(crate) fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+        if self.max_depth < self.min_depth {
+            self.min_depth = self.max_depth;
+        }
+    }
+
+    pub(crate) fn set_sort_by<F>(&mut self, cmp: F)
+    where
+        F: FnMut(&DirEntry, &DirEntry) -> Ordering + Send + Sync + 'static,
+    {
+        self.sort_by = Some(Arc::new(Mutex::new(cmp)));
+    }
+
+    pub(crate) fn walkdir<P: AsRef<Path>>(&self, root: P) -> WalkDir {
+        let mut walker = WalkDir::new(root)
+            .min_depth(self.min_depth)
+            .max_depth(self.max_depth)
+            .follow_links(self.follow_links)
+            .contents_first(self.contents_first)
+            .same_file_system(self.same_file_system);
+
+        if let Some(n) = self.max_open {
+            walker = walker.max_open(n);
+        }
+        if let Some(cmp) = &self.sort_by {
+            let cmp = Arc::clone(cmp);
+            walker = walker.sort_by(move |a, b| (cmp.lock().unwrap())(a, b));
+        }
+        for tweak in &self.tweaks {
+            walker = tweak(walker);
+        }
+        walker
+    }
+}
+
+/// The order in which a walker visits entries.
+pub(crate) enum Traversal {
+    DepthFirst(walkdir::IntoIter),
+    BreadthFirst(Box<BreadthFirst>),
+    Shard(Box<Shard>),
+}
+
+impl Traversal {
+    pub(crate) fn skip_current_dir(&mut self) {
+        match self {
+            Traversal::DepthFirst(walker) => walker.skip_current_dir(),
+            Traversal::BreadthFirst(walker) => walker.skip_current_dir(),
+            Traversal::Shard(shard) => shard.walker.skip_current_dir(),
+        }
+    }
+}
+
+impl Iterator for Traversal {
+    type Item = walkdir::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Traversal::DepthFirst(walker) => walker.next(),
+            Traversal::BreadthFirst(walker) => walker.next(),
+            Traversal::Shard(shard) => shard.next(),
+        }
+    }
+}
+
+/// A breadth-first traversal, built on top of `walkdir`.
+///
+/// Each level of the tree is yielded by a separate depth-limited walk from the root, so that
+/// entries keep their `walkdir` semantics (depth, symlink handling, loop detection and sorting).
+/// Directories that were skipped are remembered, and are not descended into by the walks of the
+/// deeper levels.
+pub(crate) struct BreadthFirst {
+    root: PathBuf,
+    config: WalkDirConfig,
+    depth: usize,
+    walker: walkdir::IntoIter,
+    /// Whether the current level has a directory that should be descended into.
+    descend: bool,
+    /// The last directory yielded, which is the one skipped by `skip_current_dir`.
+    current_dir: Option<PathBuf>,
+    pruned: HashSet<PathBuf>,
+    /// Deeper levels walk over the same entries again; this makes sure errors are reported once.
+    reported: HashSet<(Option<PathBuf>, usize)>,
+}
+
+impl BreadthFirst {
+    pub(crate) fn new(root: PathBuf, mut config: WalkDirConfig) -> Self {
+        // Directories must be yielded before their contents in order to be skippable, and the
+        // minimum depth is enforced level by level instead.
+        config.contents_first = false;
+        let depth = config.min_depth;
+        config.min_depth = 0;
+
+        BreadthFirst {
+            walker: Self::level(&root, &config, depth),
+            root,
+            config,
+            depth,
+            descend: false,
+            current_dir: None,
+            pruned: HashSet::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    fn level(root: &Path, config: &WalkDirConfig, depth: usize) -> walkdir::IntoIter {
+        config.walkdir(root).max_depth(depth).into_iter()
+    }
+
+    fn skip_current_dir(&mut self) {
+        if let Some(dir) = self.current_dir.take() {
+            self.pruned.insert(dir);
+        }
+    }
+}
+
+impl Iterator for BreadthFirst {
+    type Item = walkdir::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.current_dir = None;
+
+        loop {
+            match self.walker.next() {
+                Some(Ok(e)) if e.depth() < self.depth => {
+                    if e.file_type().is_dir() && self.pruned.contains(e.path()) {
+                        self.walker.skip_current_dir();
+                    }
+                }
+                Some(Ok(e)) => {
+                    if e.file_type().is_dir() {
+                        self.descend = true;
+                        self.current_dir = Some(e.path().to_owned());
+                    }
+                    return Some(Ok(e));
+                }
+                Some(Err(e)) => {
+                    let key = (e.path().map(Path::to_owned), e.depth());
+                    if self.reported.insert(key) {
+                        return Some(Err(e));
+                    }
+                }
+                None => {
+                    if !self.descend || self.depth >= self.config.max_depth {
+                        return None;
+                    }
+
+                    self.depth += 1;
+                    self.descend = false;
+                    self.walker = Self::level(&self.root, &self.config, self.depth);
+                }
+            }
+        }
+    }
+}
+
+/// A depth-first traversal of a part of the tree, for splitting a walk between threads.
+///
+/// The entries of the root directory are distributed between `count` shards by hashing their
+/// names; each shard yields the subtrees of the entries it was assigned. The root itself, and
+/// errors that cannot be attributed to a single subtree, are yielded by the first shard.
+///
+/// Every shard must see the entries of the root in order to pick its own, so `config` must not
+/// have a minimum depth.
+pub(crate) struct Shard {
+    walker: walkdir::IntoIter,
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    pub(crate) fn new(root: &Path, config: WalkDirConfig, index: usize, count: usize) -> Self {
+        debug_assert_eq!(config.min_depth, 0);
+        Shard {
+            walker: config.walkdir(root).into_iter(),
+            index,
+            count,
+        }
+    }
+
+    fn owns(&self, name: &OsStr) -> bool {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        hasher.finish() as usize % self.count == self.index
+    }
+}
+
+impl Iterator for Shard {
+    type Item = walkdir::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.walker.next()? {
+                Ok(e) => {
+                    if e.depth() == 1 && !self.owns(e.file_name()) {
+                        if e.file_type().is_dir() {
+                            self.walker.skip_current_dir();
+                        }
+                        continue;
+                    }
+                    if e.depth() == 0 && self.index != 0 {
+                        continue;
+                    }
+                    return Some(Ok(e));
+                }
+                Err(e) => {
+                    let owned = match (e.depth(), e.path().and_then(Path::file_name)) {
+                        (0, _) | (1, None) => self.index == 0,
+                        (1, Some(name)) => self.owns(name),
+                        // Deeper errors can only be encountered by the shard owning the subtree.
+                        _ => true,
+                    };
+                    if owned {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
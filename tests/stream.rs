@@ -0,0 +1,43 @@
+use futures::StreamExt;
+use globwalk::GlobWalkerBuilder;
+use std::collections::BTreeSet;
+use std::fs::{create_dir_all, File};
+use std::path::PathBuf;
+
+#[test]
+fn build_async_test() {
+    let dir = tempfile::TempDir::new().unwrap();
+    create_dir_all(dir.path().join("src")).unwrap();
+    for name in &["a.rs", "b.txt", "src/c.rs"] {
+        File::create(dir.path().join(name)).unwrap();
+    }
+
+    let found: BTreeSet<PathBuf> = tokio_test::block_on(async {
+        GlobWalkerBuilder::new(dir.path(), "*.rs")
+            .build_async()
+            .unwrap()
+            .map(|e| e.unwrap().into_path())
+            .collect()
+            .await
+    });
+    let expected: BTreeSet<_> = ["a.rs", "src/c.rs"]
+        .iter()
+        .map(|name| dir.path().join(name))
+        .collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn build_async_drop_test() {
+    let dir = tempfile::TempDir::new().unwrap();
+    for i in 0..200 {
+        File::create(dir.path().join(format!("{}.rs", i))).unwrap();
+    }
+
+    tokio_test::block_on(async {
+        let mut stream = GlobWalkerBuilder::new(dir.path(), "*.rs")
+            .build_async()
+            .unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+    });
+}